@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+use iced::widget::{checkbox, column, container, row, scrollable, text};
+use iced::{theme, Color, Element, Length, Theme};
+
+use crate::Message;
+
+/// Lines kept before the oldest ones are dropped.
+const CONSOLE_CAPACITY: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    Debug,
+    Info,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct LogLine {
+    level: Level,
+    text: String,
+}
+
+/// In-app copy of the log, shown in a collapsible panel under the page.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Console {
+    lines: VecDeque<LogLine>,
+    pub(crate) open: bool,
+    // Whether debug lines are captured; they're dropped entirely otherwise
+    pub(crate) verbose: bool,
+}
+
+impl Console {
+    pub(crate) fn debug(&mut self, text: impl Into<String>) {
+        self.push(Level::Debug, text.into())
+    }
+
+    pub(crate) fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text.into())
+    }
+
+    pub(crate) fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text.into())
+    }
+
+    fn push(&mut self, level: Level, text: String) {
+        if level == Level::Debug && !self.verbose {
+            return;
+        }
+
+        match level {
+            Level::Error => eprintln!("{}", text),
+            Level::Debug | Level::Info => println!("{}", text),
+        }
+
+        if self.lines.len() == CONSOLE_CAPACITY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(LogLine { level, text });
+    }
+
+    pub(crate) fn view(&self) -> Element<'_, Message> {
+        let lines = column(self.lines.iter().map(|line| {
+            let style = match line.level {
+                Level::Debug => theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)),
+                Level::Info => theme::Text::Default,
+                Level::Error => theme::Text::Color(Color::from_rgb(0.8, 0.2, 0.2)),
+            };
+
+            text(&line.text).size(13).style(style).into()
+        }));
+
+        container(
+            column![
+                row![
+                    text("Log").size(18).width(Length::Fill),
+                    checkbox("Debug lines", self.verbose).on_toggle(Message::SetVerboseLog),
+                ],
+                scrollable(lines).height(150).width(Length::Fill),
+            ]
+            .spacing(5),
+        )
+        .padding(10)
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+
+            container::Appearance::default().with_border(palette.background.strong.color, 1)
+        })
+        .into()
+    }
+}
@@ -0,0 +1,5 @@
+//! Media locations and their persisted state, usable without the GUI.
+
+pub mod format;
+pub mod media_location;
+pub mod persistence;
@@ -0,0 +1,265 @@
+use std::ops::Not;
+
+use iced::widget::{
+    button, column, container, progress_bar, row, scrollable, text, text_input, Column,
+};
+use iced::Length::Fill;
+use iced::{theme, Alignment, Border, Color, Element, Theme};
+use iced_aw::color_picker;
+use iced_aw::core::icons::bootstrap::{icon_to_text, Bootstrap};
+use media_manager::format::humanize_bytes;
+use media_manager::media_location::{MediaLocationInfo, MediaPathList};
+
+use crate::Message;
+
+const PANEL_RADIUS: f32 = 4.0;
+const OFFLINE_COLOR: Color = Color::from_rgb(0.8, 0.5, 0.1);
+
+/// Messages for a single media location.
+///
+/// The location's index is carried by the outer `Message::MediaPathMessage(usize, _)`, so variants
+/// only describe what happened to that location. Any extra data (edited text, a file index, ...)
+/// is owned by the variant rather than borrowed from the view, so messages stay `'static` and can
+/// be cloned freely.
+#[derive(Debug, Clone)]
+pub enum MediaPathMessage {
+    Remove, // Remove path
+    MoveUp,
+    MoveDown,
+    OpenColorPicker,
+    CancelColorPicker,
+    SetColor(Color),
+    ClearColor,
+    ToggleArchived,
+    Reconnect,
+    CopyPath,
+    BeginEdit,
+    EditNameChanged(String),
+    EditPathChanged(String),
+    CommitEdit,
+    CancelEdit,
+    #[allow(dead_code)]
+    ExpandAccordion,
+    #[allow(dead_code)]
+    CollapseAccordion,
+    ToggleAccordion,
+}
+
+/// The header of every location, shown on the Locations page.
+pub fn view_headers(list: &MediaPathList) -> Element<'_, Message> {
+    let last = list.len().saturating_sub(1);
+
+    if list.is_empty().not() {
+        container(
+            Column::with_children(list.iter().enumerate().map(|(i, location_info)| {
+                view_header(location_info, i == 0, i == last)
+                    .map(move |message| Message::MediaPathMessage(i, message))
+            }))
+            .spacing(10),
+        )
+    } else {
+        container(column!(text("No paths...").size(25)).height(200))
+    }
+    .padding(20)
+    .into()
+}
+
+/// Every location as a collapsible section, shown on the All Media page.
+pub fn view_media_list(list: &MediaPathList) -> Element<'_, Message> {
+    scrollable(
+        Column::with_children(list.iter().enumerate().map(|(i, location_info)| {
+            view_media(location_info).map(move |message| Message::MediaPathMessage(i, message))
+        }))
+        .spacing(10),
+    )
+    .into()
+}
+
+fn view_header(
+    location_info: &MediaLocationInfo,
+    is_first: bool,
+    is_last: bool,
+) -> Element<'_, MediaPathMessage> {
+    let move_up = if is_first {
+        None
+    } else {
+        Some(MediaPathMessage::MoveUp)
+    };
+    let move_down = if is_last {
+        None
+    } else {
+        Some(MediaPathMessage::MoveDown)
+    };
+    let capacity: Element<MediaPathMessage> = match location_info.capacity() {
+        Some(capacity) => column![
+            progress_bar(0.0..=capacity.total() as f32, capacity.used() as f32).height(6),
+            text(format!(
+                "{} free of {}",
+                humanize_bytes(capacity.available()),
+                humanize_bytes(capacity.total())
+            ))
+            .size(12),
+        ]
+        .spacing(2)
+        .into(),
+        None if location_info.is_offline() => text("Offline: path not found")
+            .size(12)
+            .style(theme::Text::Color(OFFLINE_COLOR))
+            .into(),
+        None => text("Capacity unavailable").size(12).into(),
+    };
+    let color_button = color_picker(
+        location_info.is_color_picker_open(),
+        label_color(location_info).unwrap_or(Color::WHITE),
+        button("Color").on_press(MediaPathMessage::OpenColorPicker),
+        MediaPathMessage::CancelColorPicker,
+        MediaPathMessage::SetColor,
+    );
+    let clear_color = location_info.color().map(|_| MediaPathMessage::ClearColor);
+    let archive_label = if location_info.is_archived() {
+        "Unarchive"
+    } else {
+        "Archive"
+    };
+    let label_color = label_color(location_info);
+    let archived = location_info.is_archived();
+    let text_style = if location_info.is_offline() {
+        theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))
+    } else {
+        theme::Text::Default
+    };
+
+    let (name, path): (Element<MediaPathMessage>, Element<MediaPathMessage>) =
+        match location_info.edit() {
+            Some(edit) => (
+                text_input("Name", &edit.name)
+                    .padding(5)
+                    .on_input(MediaPathMessage::EditNameChanged)
+                    .on_submit(MediaPathMessage::CommitEdit)
+                    .into(),
+                text_input("Path", &edit.path)
+                    .padding(5)
+                    .on_input(MediaPathMessage::EditPathChanged)
+                    .on_submit(MediaPathMessage::CommitEdit)
+                    .into(),
+            ),
+            None => (
+                text(location_info.name()).size(25).style(text_style).into(),
+                text(location_info.path().display())
+                    .size(15)
+                    .style(text_style)
+                    .into(),
+            ),
+        };
+    let read_only = location_info.is_read_only().then(|| {
+        row![
+            icon_to_text(Bootstrap::LockFill).size(12),
+            text("Read-only").size(12),
+        ]
+        .spacing(4)
+        .align_items(Alignment::Center)
+    });
+    let reconnect = location_info
+        .is_offline()
+        .then(|| button("Reconnect").on_press(MediaPathMessage::Reconnect));
+    let edit_buttons: Element<MediaPathMessage> = match location_info.edit() {
+        Some(edit) => row![
+            button("Save")
+                .on_press_maybe((!edit.validating).then_some(MediaPathMessage::CommitEdit)),
+            button("Cancel").on_press(MediaPathMessage::CancelEdit),
+        ]
+        .spacing(4)
+        .into(),
+        None => button("Edit").on_press(MediaPathMessage::BeginEdit).into(),
+    };
+
+    container(
+        row![
+            column![name, path, capacity,]
+                .push_maybe(read_only)
+                .push_maybe(reconnect)
+                .spacing(5)
+                .width(Fill),
+            row![
+                button("Up").on_press_maybe(move_up),
+                button("Down").on_press_maybe(move_down),
+                edit_buttons,
+                button("Copy path").on_press(MediaPathMessage::CopyPath),
+                color_button,
+                button("Clear").on_press_maybe(clear_color),
+                button(archive_label).on_press(MediaPathMessage::ToggleArchived),
+                button("Remove").on_press(MediaPathMessage::Remove)
+            ]
+            .align_items(Alignment::Center)
+            .spacing(4)
+        ]
+        .padding(4)
+        .align_items(Alignment::Center),
+    )
+    .style(move |theme: &Theme| {
+        let mut appearance = panel_appearance(theme, label_color);
+
+        // Archived locations are dimmed rather than hidden
+        if archived {
+            let text = theme.extended_palette().background.base.text;
+            appearance.text_color = Some(Color { a: 0.5, ..text });
+        }
+
+        appearance
+    })
+    .into()
+}
+
+fn label_color(location_info: &MediaLocationInfo) -> Option<Color> {
+    location_info
+        .color()
+        .map(|[r, g, b]| Color::from_rgb(r, g, b))
+}
+
+fn view_media(location_info: &MediaLocationInfo) -> Element<'_, MediaPathMessage> {
+    view_as_accordion(
+        location_info,
+        text(location_info.name()).size(25).width(Fill).into(),
+        column![text("Option1"), text("Option2")].into(),
+    )
+}
+
+fn view_as_accordion<'a>(
+    location_info: &MediaLocationInfo,
+    header: Element<'a, MediaPathMessage>,
+    body: Element<'a, MediaPathMessage>,
+) -> Element<'a, MediaPathMessage> {
+    let header = row![
+        header,
+        button("Toggle").on_press(MediaPathMessage::ToggleAccordion)
+    ]
+    .align_items(Alignment::Center);
+    let wrapper = if location_info.is_dropdown_opened() {
+        container(column![header, body].spacing(4))
+    } else {
+        container(header)
+    };
+
+    let label_color = label_color(location_info);
+
+    wrapper
+        .padding(4)
+        .width(Fill)
+        .style(move |theme: &Theme| panel_appearance(theme, label_color))
+        .into()
+}
+
+/// Shared look for the location panels on both pages; a label color replaces the border color.
+fn panel_appearance(theme: &Theme, label_color: Option<Color>) -> container::Appearance {
+    let palette = theme.extended_palette();
+
+    container::Appearance {
+        background: Some(palette.background.weak.color.into()),
+        border: Border {
+            color: label_color.unwrap_or(palette.background.strong.color),
+            width: if label_color.is_some() { 2.0 } else { 1.0 },
+            radius: PANEL_RADIUS.into(),
+        },
+        ..container::Appearance::default()
+    }
+}
@@ -1,15 +1,37 @@
-mod media_location;
-mod persistence;
+mod console;
+mod location_view;
+mod removable;
+mod settings;
+mod toast;
 
-use crate::media_location::*;
-use crate::persistence::*;
-use iced::widget::{button, column, container, row, text, text_input};
+use crate::console::Console;
+use crate::location_view::MediaPathMessage;
+use crate::settings::*;
+use crate::toast::Toasts;
+use iced::widget::{button, column, container, horizontal_space, pick_list, row, text, text_input};
 use iced::{
-    keyboard, widget, Alignment, Application, Command, Element, Pixels, Settings, Subscription,
-    Theme,
+    clipboard, event, keyboard, widget, window, Alignment, Application, Command, Element, Event,
+    Pixels, Point, Settings, Size, Subscription, Theme,
 };
+use iced_aw::floating_element::Anchor;
+use iced_aw::helpers::floating_element;
+use media_manager::media_location::*;
+use media_manager::persistence::{self, LoadError, SaveError};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const LOADING_TICK: Duration = Duration::from_millis(100);
+const TOAST_TICK: Duration = Duration::from_secs(1);
+const LOADING_TIMEOUT: Duration = Duration::from_secs(10);
+const SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+// A failed save is retried this many times, doubling the delay each time
+const SAVE_RETRIES: u32 = 3;
+// Changes are saved once nothing else has changed for this long
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+const SAVE_RETRY_DELAY: Duration = Duration::from_secs(1);
 
 static MEDIA_LOCATION_INPUT_ID: Lazy<text_input::Id> =
     Lazy::new(|| text_input::Id::new("Media Location"));
@@ -18,41 +40,208 @@ static MEDIA_LOCATION_NAME_INPUT_ID: Lazy<text_input::Id> =
 
 fn main() {
     println!("Hello, world!");
-    MediaManager::run(Settings::default()).expect("TODO: panic message");
+    let data_dir = persistence::resolve_data_dir(data_dir_flag());
+
+    MediaManager::run(Settings {
+        flags: data_dir,
+        fonts: vec![iced_aw::BOOTSTRAP_FONT_BYTES.into()],
+        window: window::Settings {
+            // Closing is handled in `update` so the window geometry can be saved first
+            exit_on_close_request: false,
+            ..window::Settings::default()
+        },
+        ..Settings::default()
+    })
+    .expect("TODO: panic message");
+}
+
+// Accepts both `--data-dir <path>` and `--data-dir=<path>`
+fn data_dir_flag() -> Option<PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--data-dir=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    None
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub(crate) struct State {
+    // Where state.json lives; resolved once at startup and never persisted itself
+    #[serde(skip)]
+    pub(crate) data_dir: PathBuf,
     #[serde(skip)]
     pub(crate) saving: bool,
+    // Set by any change that should be persisted; starts the save debounce
     #[serde(skip)]
     pub(crate) save_state_changed: bool,
+    // Bumped on every change so only the last debounce timer triggers a save
+    #[serde(skip)]
+    pub(crate) save_generation: u64,
+    // A save is due and starts as soon as no other save is in flight
+    #[serde(skip)]
+    pub(crate) save_pending: bool,
+    // Set when the last save failed; cleared only by a successful one
+    #[serde(skip)]
+    pub(crate) unsaved: bool,
+    #[serde(skip)]
+    pub(crate) save_retries: u32,
+    // The window closes once the final save finishes
+    #[serde(skip)]
+    pub(crate) closing: bool,
     pub(crate) media_path_list: MediaPathList,
     pub(crate) media_location: String,
     pub(crate) media_location_name: String,
     #[serde(skip)]
     pub(crate) media_path_error: MediaPathError,
+    #[serde(skip)]
+    pub(crate) validating_path: bool,
+    // Whether `media_location` passed the live check run while typing
+    #[serde(skip)]
+    pub(crate) media_location_valid: bool,
+    #[serde(skip)]
+    pub(crate) page: Page,
+    #[serde(default)]
+    pub(crate) window: WindowGeometry,
+    #[serde(default)]
+    pub(crate) import_settings: ImportSettings,
+    #[serde(default)]
+    pub(crate) theme: ThemePreference,
+    #[serde(default)]
+    pub(crate) backups: BackupCount,
+    #[serde(skip)]
+    pub(crate) removable_prompt: Option<PathBuf>,
+    // Mount points the user already answered this session
+    #[serde(skip)]
+    pub(crate) dismissed_mounts: HashSet<PathBuf>,
+    #[serde(skip)]
+    pub(crate) toasts: Toasts,
+    #[serde(skip)]
+    pub(crate) console: Console,
+    // File used by the location list export/import on the Settings page
+    #[serde(skip)]
+    pub(crate) location_list_file: String,
+}
+
+impl State {
+    fn save(&mut self) -> impl std::future::Future<Output = Result<(), SaveError>> {
+        self.console.debug("Saving...");
+        persistence::save(self.data_dir.clone(), self.clone(), self.backups.0)
+    }
+
+    /// Logs an error and shows it as a toast.
+    fn report_error(&mut self, text: String) {
+        self.console.error(text.clone());
+        self.toasts.push(text);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct WindowGeometry {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) position: Option<(i32, i32)>,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        let size = window::Settings::default().size;
+
+        WindowGeometry {
+            width: size.width as u32,
+            height: size.height as u32,
+            position: None,
+        }
+    }
+}
+
+impl WindowGeometry {
+    fn restore(&self) -> Command<Message> {
+        let resize = window::resize(
+            window::Id::MAIN,
+            Size::new(self.width as f32, self.height as f32),
+        );
+
+        match self.position {
+            Some((x, y)) => Command::batch(vec![
+                resize,
+                window::move_to(window::Id::MAIN, Point::new(x as f32, y as f32)),
+            ]),
+            None => resize,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Page {
+    #[default]
+    Locations,
+    AllMedia,
+    Settings,
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 enum Message {
     LoadState,
-    StateLoaded(Result<State, LoadError>),
+    LoadingTick,
+    // Boxed since State is much larger than any other message
+    StateLoaded(Result<Box<persistence::Loaded<State>>, LoadError>),
     StateSaved(Result<(), SaveError>),
+    SaveDebounced(u64),
+    RetrySave,
+    SaveNow,
     // Media Path
     AddMediaPath,
-    MediaPathMessage(usize, MediaPathMessage), //TODO: made MediaPathMessage a reference (Lifetime needed)
+    MediaPathValidated(Result<MediaLocationInfo, MediaPathError>),
+    MediaPathMessage(usize, MediaPathMessage),
+    // Result of validating a path changed by an edit, keyed by the location's path before it
+    EditValidated(PathBuf, Result<MediaLocationInfo, MediaPathError>),
 
     MediaLocationInputChanged(String),
+    MediaLocationChecked(String, Result<MediaLocationInfo, MediaPathError>),
     MediaLocationNameInputChanged(String),
+    LocationStatusesChecked(Vec<(PathBuf, LocationStatus)>),
+    Reconnected(PathBuf, LocationStatus),
+
+    RemovableDetected(PathBuf),
+    AcceptRemovable,
+    DismissRemovable,
+
+    SwitchPage(Page),
+    SetBackups(BackupCount),
+    SetTheme(ThemePreference),
+    ImportDestinationChanged(String),
+    OrganizePatternChanged(String),
 
     FocusTextID(text_input::Id),
     TabPressed { shift: bool },
+
+    WindowResized { width: u32, height: u32 },
+    WindowMoved { x: i32, y: i32 },
+    CloseRequested,
+    LocationListFileChanged(String),
+    ExportLocations,
+    LocationsExported(Result<(), SaveError>),
+    ImportLocations,
+    LocationsImported(Result<MediaPathList, LoadError>),
+    ToggleConsole,
+    SetVerboseLog(bool),
+    DismissToast(u64),
+    ToastTick(Instant),
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // Only ever one instance, so boxing State buys nothing
 enum MediaManager {
-    Loading(),
+    // Number of animation ticks since loading started
+    Loading { ticks: u32, data_dir: PathBuf },
     Loaded(State),
 }
 
@@ -60,11 +249,11 @@ impl Application for MediaManager {
     type Executor = iced::executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = PathBuf;
 
-    fn new(_: Self::Flags) -> (MediaManager, Command<Message>) {
+    fn new(data_dir: Self::Flags) -> (MediaManager, Command<Message>) {
         (
-            MediaManager::Loading(),
+            MediaManager::Loading { ticks: 0, data_dir },
             Command::perform(async {}, |_| Message::LoadState),
         )
     }
@@ -73,12 +262,39 @@ impl Application for MediaManager {
         String::from("Media Manager")
     }
 
+    fn theme(&self) -> Theme {
+        match self {
+            MediaManager::Loaded(state) => state.theme.theme(),
+            MediaManager::Loading { .. } => ThemePreference::System.theme(),
+        }
+    }
+
     fn update(&mut self, message: Self::Message) -> Command<Message> {
         match self {
             MediaManager::Loaded(state) => {
                 let command = match message {
                     Message::MediaLocationInputChanged(new_text) => {
                         state.media_location = new_text;
+                        Some(check_media_location(state))
+                    }
+                    Message::MediaLocationChecked(location, result) => {
+                        // Ignore results for text that has since been edited
+                        if location == state.media_location {
+                            state
+                                .console
+                                .debug(format!("Checked {:?}: {:?}", location, result));
+                            let duplicate = result
+                                .as_ref()
+                                .is_ok_and(|checked| state.media_path_list.contains(checked));
+                            state.media_location_valid = result.is_ok() && !duplicate;
+
+                            // Say why Add is disabled, since it can't be pressed to find out
+                            if duplicate {
+                                state.media_path_error = MediaPathError::Duplicate;
+                            } else if let MediaPathError::Duplicate = state.media_path_error {
+                                state.media_path_error = MediaPathError::NoError;
+                            }
+                        }
                         None
                     }
                     Message::MediaLocationNameInputChanged(new_text) => {
@@ -86,25 +302,125 @@ impl Application for MediaManager {
                         Some(Command::none())
                     }
                     Message::AddMediaPath => {
-                        match MediaLocationInfo::new(
-                            state.media_location_name.clone(),
-                            state.media_location.clone(),
-                        ) {
-                            Ok(location_info) => {
-                                state.media_path_list.push(location_info);
+                        if state.validating_path {
+                            None
+                        } else {
+                            state.validating_path = true;
+                            Some(Command::perform(
+                                MediaLocationInfo::new_async(
+                                    state.media_location_name.clone(),
+                                    state.media_location.clone(),
+                                ),
+                                Message::MediaPathValidated,
+                            ))
+                        }
+                    }
+                    Message::MediaPathValidated(result) => {
+                        state.validating_path = false;
+                        let result = result.and_then(|location_info| {
+                            state.media_path_list.try_push(location_info)
+                        });
+                        match result {
+                            Ok(()) => {
                                 state.media_location.clear();
                                 state.media_location_name.clear();
+                                state.media_location_valid = false;
                                 state.media_path_error = MediaPathError::NoError;
                                 state.save_state_changed = true;
                                 Some(text_input::focus(MEDIA_LOCATION_NAME_INPUT_ID.clone()))
                             }
                             Err(err) => {
-                                eprintln!("Media error: {:?}", err);
+                                state.report_error(format!("Couldn't add location: {}", err));
                                 state.media_path_error = err;
                                 None
                             }
                         }
                     }
+                    Message::LocationStatusesChecked(statuses) => {
+                        for (path, status) in statuses {
+                            state.media_path_list.set_status(&path, status);
+                        }
+                        None
+                    }
+                    Message::Reconnected(path, status) => {
+                        state.media_path_list.set_status(&path, status);
+                        if status.is_offline() {
+                            state.report_error(String::from("Location is still offline"));
+                        }
+                        None
+                    }
+                    Message::RemovableDetected(path) => {
+                        state
+                            .console
+                            .debug(format!("Removable media mounted at {:?}", path));
+                        if state.removable_prompt.is_none()
+                            && !state.dismissed_mounts.contains(&path)
+                            && !state.media_path_list.contains_path(&path)
+                        {
+                            state.removable_prompt = Some(path);
+                        }
+                        None
+                    }
+                    Message::AcceptRemovable => {
+                        if let Some(path) = state.removable_prompt.take() {
+                            state.media_location = path.to_string_lossy().into_owned();
+                            state.media_location_name = path
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_default();
+                            state.dismissed_mounts.insert(path);
+                            state.page = Page::Locations;
+                        }
+                        Some(Command::batch(vec![
+                            check_media_location(state),
+                            text_input::focus(MEDIA_LOCATION_NAME_INPUT_ID.clone()),
+                        ]))
+                    }
+                    Message::DismissRemovable => {
+                        if let Some(path) = state.removable_prompt.take() {
+                            state.dismissed_mounts.insert(path);
+                        }
+                        None
+                    }
+                    Message::SwitchPage(page) => {
+                        state.page = page;
+                        None
+                    }
+                    Message::SetBackups(backups) => {
+                        state.backups = backups;
+                        state.save_state_changed = true;
+                        None
+                    }
+                    Message::SetTheme(theme) => {
+                        state.theme = theme;
+                        state.save_state_changed = true;
+                        None
+                    }
+                    Message::ImportDestinationChanged(new_text) => {
+                        state.import_settings.destination = new_text;
+                        state.save_state_changed = true;
+                        None
+                    }
+                    Message::OrganizePatternChanged(new_text) => {
+                        state.import_settings.pattern = new_text;
+                        state.save_state_changed = true;
+                        None
+                    }
+                    Message::WindowResized { width, height } => {
+                        state.window.width = width;
+                        state.window.height = height;
+                        None
+                    }
+                    Message::WindowMoved { x, y } => {
+                        state.window.position = Some((x, y));
+                        None
+                    }
+                    Message::CloseRequested => {
+                        // Queued like any other save, so it can't run alongside one in flight
+                        state.closing = true;
+                        state.save_pending = true;
+                        None
+                    }
                     Message::FocusTextID(id) => Some(text_input::focus(id)),
                     Message::TabPressed { shift } => {
                         if shift {
@@ -114,10 +430,95 @@ impl Application for MediaManager {
                         }
                     }
                     Message::MediaPathMessage(index, message) => {
+                        let mut command = None;
                         match message {
                             MediaPathMessage::Remove => {
                                 state.media_path_list.remove(index);
                                 state.save_state_changed = true;
+                                // The typed path may have been a duplicate of this location
+                                command = Some(check_media_location(state));
+                            }
+                            MediaPathMessage::MoveUp => {
+                                state.media_path_list.move_up(index);
+                                state.save_state_changed = true;
+                            }
+                            MediaPathMessage::MoveDown => {
+                                state.media_path_list.move_down(index);
+                                state.save_state_changed = true;
+                            }
+                            MediaPathMessage::OpenColorPicker => {
+                                state.media_path_list.set_color_picker_open(index, true)
+                            }
+                            MediaPathMessage::CancelColorPicker => {
+                                state.media_path_list.set_color_picker_open(index, false)
+                            }
+                            MediaPathMessage::SetColor(color) => {
+                                state
+                                    .media_path_list
+                                    .set_color(index, Some([color.r, color.g, color.b]));
+                                state.save_state_changed = true;
+                            }
+                            MediaPathMessage::ClearColor => {
+                                state.media_path_list.set_color(index, None);
+                                state.save_state_changed = true;
+                            }
+                            MediaPathMessage::ToggleArchived => {
+                                state.media_path_list.toggle_archived(index);
+                                state.save_state_changed = true;
+                            }
+                            MediaPathMessage::CopyPath => {
+                                if let Some(location_info) = state.media_path_list.get(index) {
+                                    let path = location_info.path().to_string_lossy().into_owned();
+                                    command = Some(clipboard::write(path));
+                                }
+                            }
+                            MediaPathMessage::Reconnect => {
+                                if let Some(location_info) = state.media_path_list.get(index) {
+                                    let path = location_info.path().to_path_buf();
+                                    command = Some(Command::perform(
+                                        LocationStatus::query_async(path.clone()),
+                                        move |status| Message::Reconnected(path, status),
+                                    ));
+                                }
+                            }
+                            MediaPathMessage::BeginEdit => state.media_path_list.begin_edit(index),
+                            MediaPathMessage::EditNameChanged(name) => {
+                                state.media_path_list.set_edit_name(index, name)
+                            }
+                            MediaPathMessage::EditPathChanged(path) => {
+                                state.media_path_list.set_edit_path(index, path)
+                            }
+                            MediaPathMessage::CommitEdit => {
+                                match state.media_path_list.commit_edit(index) {
+                                    Ok(None) => {
+                                        state.media_path_error = MediaPathError::NoError;
+                                        state.save_state_changed = true;
+                                    }
+                                    Ok(Some(edit)) => {
+                                        // The result is matched back by path, since the location
+                                        // may be moved or removed while it's validated
+                                        let original = state
+                                            .media_path_list
+                                            .get(index)
+                                            .expect("Invalid Index!")
+                                            .path()
+                                            .to_path_buf();
+                                        command = Some(Command::perform(
+                                            MediaLocationInfo::new_async(edit.name, edit.path),
+                                            move |result| Message::EditValidated(original, result),
+                                        ));
+                                    }
+                                    Err(err) => {
+                                        state.report_error(format!(
+                                            "Couldn't rename location: {}",
+                                            err
+                                        ));
+                                        state.media_path_error = err;
+                                    }
+                                }
+                            }
+                            MediaPathMessage::CancelEdit => {
+                                state.media_path_list.cancel_edit(index)
                             }
                             MediaPathMessage::ExpandAccordion => {
                                 state.media_path_list.expand_accordion(index)
@@ -129,128 +530,268 @@ impl Application for MediaManager {
                                 state.media_path_list.toggle_accordion(index)
                             }
                         }
+                        command
+                    }
+                    Message::EditValidated(original, result) => {
+                        match state.media_path_list.finish_edit(&original, result) {
+                            Ok(true) => {
+                                state.media_path_error = MediaPathError::NoError;
+                                state.save_state_changed = true;
+                                // The typed path may now be free, or newly taken
+                                Some(check_media_location(state))
+                            }
+                            Ok(false) => None,
+                            Err(err) => {
+                                state.report_error(format!(
+                                    "Couldn't change location path: {}",
+                                    err
+                                ));
+                                state.media_path_error = err;
+                                None
+                            }
+                        }
+                    }
+                    Message::LocationListFileChanged(file) => {
+                        state.location_list_file = file;
+                        None
+                    }
+                    Message::ExportLocations => Some(Command::perform(
+                        persistence::export_locations(
+                            expand_location(&state.location_list_file),
+                            state.media_path_list.clone(),
+                        ),
+                        Message::LocationsExported,
+                    )),
+                    Message::LocationsExported(result) => {
+                        match result {
+                            Ok(()) => state.console.info("Exported locations"),
+                            Err(e) => {
+                                state.report_error(format!("Couldn't export locations: {}", e))
+                            }
+                        }
+                        None
+                    }
+                    Message::ImportLocations => Some(Command::perform(
+                        persistence::import_locations(expand_location(&state.location_list_file)),
+                        Message::LocationsImported,
+                    )),
+                    Message::LocationsImported(result) => match result {
+                        Ok(list) => {
+                            let added = state.media_path_list.merge(list);
+                            state
+                                .console
+                                .info(format!("Imported {} new locations", added));
+                            state.save_state_changed = added > 0;
+                            (added > 0).then(|| check_media_location(state))
+                        }
+                        Err(e) => {
+                            state.report_error(format!("Couldn't import locations: {}", e));
+                            None
+                        }
+                    },
+                    Message::ToggleConsole => {
+                        state.console.open = !state.console.open;
+                        None
+                    }
+                    Message::SetVerboseLog(verbose) => {
+                        state.console.verbose = verbose;
+                        None
+                    }
+                    Message::DismissToast(id) => {
+                        state.toasts.dismiss(id);
                         None
                     }
+                    Message::ToastTick(now) => {
+                        state.toasts.expire(now);
+                        None
+                    }
+                    Message::StateSaved(result) if state.closing => {
+                        state.saving = false;
+                        if let Err(e) = result {
+                            state
+                                .console
+                                .error(format!("Couldn't save before closing: {}", e));
+                        }
+                        // If closing was requested mid-save, the final save starts below first
+                        (!state.save_pending).then(|| window::close(window::Id::MAIN))
+                    }
                     Message::StateSaved(result) => {
                         state.saving = false;
                         match result {
+                            Err(e) if state.save_retries < SAVE_RETRIES => {
+                                let delay = SAVE_RETRY_DELAY * 2u32.pow(state.save_retries);
+                                state.save_retries += 1;
+                                state.unsaved = true;
+                                state.console.error(format!(
+                                    "Couldn't save: {}, retrying in {}s",
+                                    e,
+                                    delay.as_secs()
+                                ));
+                                Some(Command::perform(async_std::task::sleep(delay), |_| {
+                                    Message::RetrySave
+                                }))
+                            }
                             Err(e) => {
-                                eprintln!("Saving Error: {:?}", e);
+                                state.unsaved = true;
+                                state.report_error(format!("Couldn't save: {}", e));
+                                None
                             }
                             Ok(_) => {
-                                println!("Saved state!")
+                                state.unsaved = false;
+                                state.save_retries = 0;
+                                state.console.info("Saved state!");
+                                None
                             }
                         }
+                    }
+                    Message::SaveDebounced(generation) => {
+                        if generation == state.save_generation {
+                            state.save_pending = true;
+                        }
+                        None
+                    }
+                    Message::RetrySave => {
+                        state.save_pending = true;
+                        None
+                    }
+                    Message::SaveNow => {
+                        state.save_retries = 0;
+                        state.save_pending = true;
                         None
                     }
                     _ => None,
                 };
 
-                match (command, state.saving, state.save_state_changed) {
-                    (None, false, true) => {
-                        state.saving = true;
-                        state.save_state_changed = false;
-                        Command::perform(state.clone().save(), Message::StateSaved)
-                    }
-                    (Some(command), false, true) => {
-                        state.saving = true;
-                        state.save_state_changed = false;
-                        Command::batch(vec![
-                            command,
-                            Command::perform(state.clone().save(), Message::StateSaved),
-                        ])
-                    }
-                    (Some(command), _, false) => command,
-                    _ => Command::none(),
-                }
+                // Every change restarts the debounce timer; only the newest one starts a save
+                let debounce = state.save_state_changed.then(|| {
+                    state.save_state_changed = false;
+                    state.save_generation += 1;
+                    let generation = state.save_generation;
+                    Command::perform(async_std::task::sleep(SAVE_DEBOUNCE), move |_| {
+                        Message::SaveDebounced(generation)
+                    })
+                });
+
+                // A save requested while another is in flight waits for its StateSaved
+                let save = (state.save_pending && !state.saving).then(|| {
+                    state.saving = true;
+                    state.save_pending = false;
+                    Command::perform(state.save(), Message::StateSaved)
+                });
+
+                Command::batch(command.into_iter().chain(debounce).chain(save))
             }
-            MediaManager::Loading() => {
-                return match message {
-                    Message::LoadState => Command::perform(State::load(), Message::StateLoaded),
-                    Message::StateLoaded(restored_state) => {
-                        match restored_state {
-                            Ok(state) => {
-                                println!("State successfully loaded.");
-                                *self = MediaManager::Loaded(state);
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to restore state: {:?}", e);
-                                *self = MediaManager::Loaded(State::default());
+            MediaManager::Loading { ticks, data_dir } => match message {
+                Message::LoadState => {
+                    Command::perform(persistence::load(data_dir.clone()), |result| {
+                        Message::StateLoaded(result.map(Box::new))
+                    })
+                }
+                Message::LoadingTick => {
+                    *ticks += 1;
+                    Command::none()
+                }
+                Message::StateLoaded(restored_state) => match restored_state {
+                    Ok(loaded) => {
+                        let persistence::Loaded {
+                            mut state,
+                            recovered_from,
+                            recovered_because,
+                        } = *loaded;
+                        state.data_dir = data_dir.clone();
+                        match (recovered_from, recovered_because) {
+                            (Some(backup), Some(because)) => state.report_error(format!(
+                                "Saved state couldn't be read ({}); recovered it from {}",
+                                because,
+                                backup.display()
+                            )),
+                            _ => state.console.info("State successfully loaded."),
+                        }
+                        let commands = vec![
+                            state.window.restore(),
+                            check_media_location(&state),
+                            Command::perform(
+                                LocationStatus::query_all(state.media_path_list.paths()),
+                                Message::LocationStatusesChecked,
+                            ),
+                        ];
+                        *self = MediaManager::Loaded(state);
+                        Command::batch(commands)
+                    }
+                    Err(e) => {
+                        let mut state = State {
+                            data_dir: data_dir.clone(),
+                            ..State::default()
+                        };
+                        // A missing file is just a first run; only an unreadable one is worth
+                        // telling the user about
+                        match e {
+                            LoadError::Format(_) | LoadError::Version(_) => {
+                                state.report_error(format!(
+                                    "Saved state couldn't be used ({}); it was backed up and \
+                                     the app started fresh",
+                                    e
+                                ))
                             }
+                            LoadError::File => state.console.info("No saved state, starting fresh"),
                         }
+                        *self = MediaManager::Loaded(state);
                         Command::none()
                     }
-                    _ => Command::none(),
-                }
-            }
+                },
+                Message::CloseRequested => window::close(window::Id::MAIN),
+                _ => Command::none(),
+            },
         }
     }
 
-    fn view(&self) -> Element<Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
         match self {
             MediaManager::Loaded(state) => {
-                // Get a view of the currently saved paths
-                let paths_view = container(state.media_path_list.view_headers());
-                let media_view = container(state.media_path_list.view_media());
-                let path_info_valid = state.media_location.starts_with('/');
-                let button_action = if path_info_valid {
-                    Some(Message::AddMediaPath)
-                } else {
-                    None
+                let page = match state.page {
+                    Page::Locations => view_locations(state),
+                    Page::AllMedia => {
+                        container(location_view::view_media_list(&state.media_path_list))
+                            .padding(20)
+                            .into()
+                    }
+                    Page::Settings => view_settings(state),
                 };
 
-                let err_text = match state.media_path_error {
-                    MediaPathError::NoError => "",
-                    MediaPathError::InvalidPath => "Invalid path",
-                    MediaPathError::PathDoesNotExist => "Path does not exist",
-                    MediaPathError::NoPermission => "No permission",
-                    MediaPathError::NotADirectory => "Not a directory",
-                };
+                let console = state.console.open.then(|| state.console.view());
+                let content = column![
+                    view_navigation(state.page, state.theme, state.unsaved),
+                    page
+                ]
+                .push_maybe(console);
 
-                let add_media_path_view = column![
-                    text("Media Location Info"),
-                    text_input("SD Card", &state.media_location_name)
-                        .width(440)
-                        .padding(10)
-                        .on_input(Message::MediaLocationNameInputChanged)
-                        .on_submit(Message::FocusTextID(MEDIA_LOCATION_INPUT_ID.clone()))
-                        .id(MEDIA_LOCATION_NAME_INPUT_ID.clone()),
-                    text_input("/media/...", &state.media_location)
-                        .width(440)
-                        .padding(10)
-                        .on_input(Message::MediaLocationInputChanged)
-                        .on_submit(Message::AddMediaPath)
-                        .id(MEDIA_LOCATION_INPUT_ID.clone()),
-                    // The increment button. We tell it to produce an
-                    // `Increment` message when pressed
-                    button("Add").on_press_maybe(button_action).width(120),
-                    // We show the value of the counter here
-                    text(String::from(err_text)).size(50),
-                    // The decrement button. We tell it to produce a
-                    // `Decrement` message when pressed
-                    //button("Remove").on_press(Message::Remove),
-                ] // column![]
-                .spacing(10)
-                .padding(20)
-                .align_items(Alignment::Start);
-
-                //let sidebar_size = if add_media_path_view.size().width
-
-                row!(
-                    column![add_media_path_view, paths_view]
-                        .width(iced::Length::FillPortion(1).enclose(Pixels(80.0).into())),
-                    container(media_view).width(iced::Length::FillPortion(2))
-                )
-                .into()
+                floating_element(content, state.toasts.view())
+                    .anchor(Anchor::SouthEast)
+                    .offset(20.0)
+                    .hide(state.toasts.is_empty())
+                    .into()
             }
-            _ => container(text("Loading...")).into(),
+            MediaManager::Loading { ticks, .. } => view_loading(*ticks),
         }
     }
 
     fn subscription(&self) -> Subscription<Message> {
         use iced::keyboard::key;
 
-        keyboard::on_key_press(|key, modifiers| {
+        let window_events = event::listen_with(|event, _status| match event {
+            Event::Window(window::Id::MAIN, window::Event::Resized { width, height }) => {
+                Some(Message::WindowResized { width, height })
+            }
+            Event::Window(window::Id::MAIN, window::Event::Moved { x, y }) => {
+                Some(Message::WindowMoved { x, y })
+            }
+            Event::Window(window::Id::MAIN, window::Event::CloseRequested) => {
+                Some(Message::CloseRequested)
+            }
+            _ => None,
+        });
+
+        let key_presses = keyboard::on_key_press(|key, modifiers| {
             let keyboard::Key::Named(key) = key else {
                 return None;
             };
@@ -261,6 +802,360 @@ impl Application for MediaManager {
                 }),
                 _ => None,
             }
-        })
+        });
+
+        let toast_ticks = match self {
+            MediaManager::Loaded(state) if !state.toasts.is_empty() => {
+                iced::time::every(TOAST_TICK).map(Message::ToastTick)
+            }
+            _ => Subscription::none(),
+        };
+
+        let loading_ticks = match self {
+            MediaManager::Loading { .. } => {
+                iced::time::every(LOADING_TICK).map(|_| Message::LoadingTick)
+            }
+            MediaManager::Loaded(_) => Subscription::none(),
+        };
+
+        Subscription::batch(vec![
+            window_events,
+            key_presses,
+            loading_ticks,
+            toast_ticks,
+            removable::watch(),
+        ])
+    }
+}
+
+fn check_media_location(state: &State) -> Command<Message> {
+    let location = state.media_location.clone();
+
+    Command::perform(
+        MediaLocationInfo::new_async(String::new(), location.clone()),
+        move |result| Message::MediaLocationChecked(location, result),
+    )
+}
+
+fn view_loading(ticks: u32) -> Element<'static, Message> {
+    let frame = SPINNER_FRAMES[ticks as usize % SPINNER_FRAMES.len()];
+    let timed_out = LOADING_TICK * ticks > LOADING_TIMEOUT;
+
+    let status = if timed_out {
+        text(format!(
+            "Loading is taking longer than {}s. The data directory may be on a slow or \
+             unavailable disk.",
+            LOADING_TIMEOUT.as_secs()
+        ))
+    } else {
+        text("")
+    };
+
+    container(column![text(format!("{} Loading...", frame)), status].spacing(10))
+        .padding(20)
+        .into()
+}
+
+fn view_navigation(
+    current: Page,
+    theme: ThemePreference,
+    unsaved: bool,
+) -> Element<'static, Message> {
+    let tab = |label, page| {
+        button(label).on_press_maybe((current != page).then_some(Message::SwitchPage(page)))
+    };
+    let theme_toggle_label = match theme.toggled() {
+        ThemePreference::Light => "Light mode",
+        _ => "Dark mode",
+    };
+
+    row![
+        tab("Locations", Page::Locations),
+        tab("All Media", Page::AllMedia),
+        tab("Settings", Page::Settings),
+        horizontal_space(),
+    ]
+    .push_maybe(unsaved.then(|| text("Unsaved changes")))
+    .push_maybe(unsaved.then(|| button("Save now").on_press(Message::SaveNow)))
+    .push(button("Log").on_press(Message::ToggleConsole))
+    .push(button(theme_toggle_label).on_press(Message::SetTheme(theme.toggled())))
+    .align_items(Alignment::Center)
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+fn view_settings(state: &State) -> Element<'_, Message> {
+    let settings = &state.import_settings;
+    let location_list_action =
+        |message| (!state.location_list_file.trim().is_empty()).then_some(message);
+
+    let pattern_error = match validate_pattern(&settings.pattern) {
+        Ok(()) => String::new(),
+        Err(PatternError::UnknownToken(token)) => format!("Unknown token {{{}}}", token),
+        Err(PatternError::UnclosedBrace) => String::from("Missing closing '}'"),
+        Err(PatternError::UnopenedBrace) => String::from("Missing opening '{'"),
+        Err(PatternError::NestedBrace) => String::from("Braces can't be nested"),
+    };
+    let available_tokens = PATTERN_TOKENS
+        .iter()
+        .map(|token| format!("{{{}}}", token))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    column![
+        text("Appearance").size(25),
+        text("Theme"),
+        pick_list(ThemePreference::ALL, Some(state.theme), Message::SetTheme),
+        text("Storage").size(25),
+        text("Backups of saved state"),
+        pick_list(
+            BackupCount::CHOICES,
+            Some(state.backups),
+            Message::SetBackups
+        ),
+        text("Import").size(25),
+        text("Default destination"),
+        text_input("/home/.../Pictures", &settings.destination)
+            .width(440)
+            .padding(10)
+            .on_input(Message::ImportDestinationChanged),
+        text("Organize pattern"),
+        text_input("{year}/{month}/{day}", &settings.pattern)
+            .width(440)
+            .padding(10)
+            .on_input(Message::OrganizePatternChanged),
+        text(pattern_error),
+        text(format!("Available tokens: {}", available_tokens)).size(15),
+        text("Location list").size(25),
+        text("Export the locations to a file, or import ones exported on another machine"),
+        text_input("~/media_locations.json", &state.location_list_file)
+            .width(440)
+            .padding(10)
+            .on_input(Message::LocationListFileChanged),
+        row![
+            button("Export").on_press_maybe(location_list_action(Message::ExportLocations)),
+            button("Import").on_press_maybe(location_list_action(Message::ImportLocations)),
+        ]
+        .spacing(10),
+    ]
+    .spacing(10)
+    .padding(20)
+    .into()
+}
+
+fn view_locations(state: &State) -> Element<'_, Message> {
+    // Get a view of the currently saved paths
+    let paths_view = container(location_view::view_headers(&state.media_path_list));
+    let first_run = state.media_path_list.is_empty();
+    let media_view: Element<Message> = if first_run {
+        view_first_run()
+    } else {
+        location_view::view_media_list(&state.media_path_list)
+    };
+    // The name may be left blank (it's derived from the path), so only the path gates adding
+    let path_info_valid = state.media_location_valid && !state.validating_path;
+    let button_action = if path_info_valid {
+        Some(Message::AddMediaPath)
+    } else {
+        None
+    };
+
+    let err_text = state.media_path_error.to_string();
+
+    let removable_prompt: Element<Message> = match &state.removable_prompt {
+        Some(path) => column![
+            text(format!("Add {} as a location?", path.display())),
+            row![
+                button("Add").on_press(Message::AcceptRemovable),
+                button("Dismiss").on_press(Message::DismissRemovable),
+            ]
+            .spacing(4),
+        ]
+        .spacing(4)
+        .into(),
+        None => column![].into(),
+    };
+
+    let media_location_inputs = container(
+        column![
+            text_input("SD Card", &state.media_location_name)
+                .width(440)
+                .padding(10)
+                .on_input(Message::MediaLocationNameInputChanged)
+                .on_submit(Message::FocusTextID(MEDIA_LOCATION_INPUT_ID.clone()))
+                .id(MEDIA_LOCATION_NAME_INPUT_ID.clone()),
+            text_input("/media/...", &state.media_location)
+                .width(440)
+                .padding(10)
+                .on_input(Message::MediaLocationInputChanged)
+                .on_submit(Message::AddMediaPath)
+                .id(MEDIA_LOCATION_INPUT_ID.clone()),
+        ]
+        .spacing(10),
+    )
+    // Point first-time users at the inputs they need to fill in
+    .style(move |theme: &Theme| {
+        if first_run {
+            container::Appearance::default()
+                .with_border(theme.extended_palette().primary.strong.color, 2)
+        } else {
+            container::Appearance::default()
+        }
+    });
+
+    let add_media_path_view = column![
+        removable_prompt,
+        text("Media Location Info"),
+        media_location_inputs,
+        // The increment button. We tell it to produce an
+        // `Increment` message when pressed
+        button("Add").on_press_maybe(button_action).width(120),
+        // We show the value of the counter here
+        text(err_text).size(50),
+        // The decrement button. We tell it to produce a
+        // `Decrement` message when pressed
+        //button("Remove").on_press(Message::Remove),
+    ] // column![]
+    .spacing(10)
+    .padding(20)
+    .align_items(Alignment::Start);
+
+    //let sidebar_size = if add_media_path_view.size().width
+
+    row!(
+        column![add_media_path_view, paths_view]
+            .width(iced::Length::FillPortion(1).enclose(Pixels(80.0).into())),
+        container(media_view).width(iced::Length::FillPortion(2))
+    )
+    .into()
+}
+
+fn view_first_run() -> Element<'static, Message> {
+    container(
+        column![
+            text("Welcome to Media Manager").size(30),
+            text("Start by adding a media location, such as an SD card or a photo folder:"),
+            text("1. Give it a name in the first box, e.g. \"SD Card\"."),
+            text("2. Enter the absolute path to the folder in the second box."),
+            text("3. Press Add. The location will show up here."),
+        ]
+        .spacing(10),
+    )
+    .padding(20)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::task::block_on;
+
+    fn location(root: &std::path::Path, dir: &str, name: &str) -> MediaLocationInfo {
+        let path = root.join(dir);
+        std::fs::create_dir_all(&path).unwrap();
+        MediaLocationInfo::new(name.to_string(), path.to_string_lossy().into_owned()).unwrap()
+    }
+
+    #[test]
+    fn state_round_trip_keeps_settings_and_resets_skipped_fields() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let media = tempfile::tempdir().unwrap();
+
+        let mut state = State {
+            data_dir: data_dir.path().to_path_buf(),
+            saving: true,
+            unsaved: true,
+            save_retries: 2,
+            closing: true,
+            media_location: String::from("~/Pictures"),
+            media_location_name: String::from("Pictures"),
+            media_path_error: MediaPathError::Duplicate,
+            media_location_valid: true,
+            page: Page::Settings,
+            window: WindowGeometry {
+                width: 1280,
+                height: 720,
+                position: Some((-40, 25)),
+            },
+            import_settings: ImportSettings {
+                destination: String::from("/srv/photos"),
+                pattern: String::from("{camera}/{year}"),
+            },
+            theme: ThemePreference::Dark,
+            backups: BackupCount(5),
+            removable_prompt: Some(PathBuf::from("/media/card")),
+            location_list_file: String::from("locations.json"),
+            ..State::default()
+        };
+        state
+            .media_path_list
+            .push(location(media.path(), "sd card", "SD Card"));
+        state
+            .media_path_list
+            .push(location(media.path(), "Fotos \"Ürlaub\" #1", "Ürlaub 📷"));
+        state.media_path_list.set_color(1, Some([0.25, 0.5, 1.0]));
+        state.media_path_list.toggle_archived(0);
+        state.media_path_list.begin_edit(0);
+
+        block_on(state.save()).unwrap();
+        let loaded = block_on(persistence::load::<State>(state.data_dir.clone()))
+            .unwrap()
+            .state;
+
+        assert_eq!(loaded.media_location, state.media_location);
+        assert_eq!(loaded.media_location_name, state.media_location_name);
+        assert_eq!(loaded.window.width, 1280);
+        assert_eq!(loaded.window.height, 720);
+        assert_eq!(loaded.window.position, Some((-40, 25)));
+        assert_eq!(loaded.import_settings.destination, "/srv/photos");
+        assert_eq!(loaded.import_settings.pattern, "{camera}/{year}");
+        assert_eq!(loaded.theme, ThemePreference::Dark);
+        assert_eq!(loaded.backups, BackupCount(5));
+        assert_eq!(loaded.media_path_list.len(), 2);
+        for (loaded, saved) in loaded
+            .media_path_list
+            .iter()
+            .zip(state.media_path_list.iter())
+        {
+            assert_eq!(loaded.name(), saved.name());
+            assert_eq!(loaded.path(), saved.path());
+            assert_eq!(loaded.color(), saved.color());
+            assert_eq!(loaded.is_archived(), saved.is_archived());
+            assert!(loaded.edit().is_none());
+        }
+
+        // Session-only fields start fresh rather than being restored
+        assert_eq!(loaded.data_dir, PathBuf::new());
+        assert!(!loaded.saving && !loaded.unsaved && !loaded.closing);
+        assert_eq!(loaded.save_retries, 0);
+        assert!(matches!(loaded.media_path_error, MediaPathError::NoError));
+        assert!(!loaded.media_location_valid);
+        assert_eq!(loaded.page, Page::Locations);
+        assert!(loaded.removable_prompt.is_none());
+        assert!(loaded.location_list_file.is_empty());
+    }
+
+    #[test]
+    fn state_without_settings_loads_defaults() {
+        let data_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            persistence::state_path(data_dir.path()),
+            r#"{ "version": 1, "media_path_list": { "list": [] },
+                 "media_location": "", "media_location_name": "" }"#,
+        )
+        .unwrap();
+
+        let loaded = block_on(persistence::load::<State>(data_dir.path().to_path_buf()))
+            .unwrap()
+            .state;
+
+        let window = WindowGeometry::default();
+        assert_eq!(loaded.window.width, window.width);
+        assert_eq!(loaded.window.height, window.height);
+        assert_eq!(loaded.window.position, None);
+        assert_eq!(loaded.import_settings.pattern, "{year}/{month}/{day}");
+        assert_eq!(loaded.theme, ThemePreference::System);
+        assert_eq!(loaded.backups, BackupCount(persistence::DEFAULT_BACKUPS));
     }
 }
@@ -1,120 +1,466 @@
-use std::ops::Not;
+use std::fmt;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
-use iced::{Alignment, Element, Theme};
-use iced::Length::Fill;
-use iced::widget::{button, column, Column, container, row, scrollable, text};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::media_location::MediaPathError::*;
-use crate::Message;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaLocationInfo {
     name: String,
+    #[serde(
+        serialize_with = "serialize_path_buf",
+        deserialize_with = "deserialize_path_buf"
+    )]
     path: PathBuf,
-    #[serde(skip)]
+    #[serde(default)]
     dropdown_opened: bool,
+    #[serde(skip)]
+    capacity: Option<Capacity>,
+    // Set when a persisted path can't be found (e.g. the card isn't plugged in); the location is
+    // kept and shown as offline until it's reconnected
+    #[serde(skip)]
+    offline: bool,
+    // Read-only mounts and locked folders; anything that would write here is disabled
+    #[serde(skip)]
+    read_only: bool,
+    #[serde(default)]
+    color: Option<[f32; 3]>,
+    #[serde(skip)]
+    color_picker_open: bool,
+    #[serde(default)]
+    archived: bool,
+    // Name and path being typed while the header is in edit mode
+    #[serde(skip)]
+    edit: Option<LocationEdit>,
+    // Which directory the path is on its filesystem, when it could be queried
+    #[serde(skip)]
+    file_id: Option<FileId>,
+}
+
+/// The text fields of a location header in edit mode.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocationEdit {
+    pub name: String,
+    pub path: String,
+    // Set while a committed path change is being validated
+    pub validating: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
-pub enum MediaPathMessage {
-    Remove, // Remove path
-    ExpandAccordion,
-    CollapseAccordion,
-    ToggleAccordion,
+pub struct Capacity {
+    available: u64,
+    total: u64,
+}
+
+impl Capacity {
+    // Returns None when the filesystem can't be queried (e.g. the card was removed)
+    pub fn query(path: &Path) -> Option<Capacity> {
+        match (fs2::available_space(path), fs2::total_space(path)) {
+            (Ok(available), Ok(total)) => Some(Capacity { available, total }),
+            (Err(err), _) | (_, Err(err)) => {
+                eprintln!("Failed to stat {:?}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    pub fn available(&self) -> u64 {
+        self.available
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
 }
 
 impl MediaLocationInfo {
     // TODO: Somehow let this assume ownership of the parameters
     pub fn new(name: String, location: String) -> Result<MediaLocationInfo, MediaPathError> {
-        return match Path::new(&location).canonicalize() {
-            Ok(path) => {
-                match path.try_exists() {
-                    // Returns true, false, and Err (Err means cannot be determined due to permissions)
-                    Ok(b) => {
-                        if b {
-                            if path.is_dir() {
-                                Ok(MediaLocationInfo {
-                                    name,
-                                    path,
-                                    dropdown_opened: false,
-                                })
-                            } else {
-                                Err(NotADirectory)
-                            }
-                        } else {
-                            Err(PathDoesNotExist)
-                        }
-                    }
-                    Err(_err) => Err(NoPermission),
-                }
-            }
+        // Checked before expanding, which would resolve an empty path to the home directory
+        if location.trim().is_empty() {
+            return Err(InvalidPath);
+        }
+
+        match expand_location(&location).canonicalize() {
+            Ok(path) => Self::from_canonical(name, path),
+            Err(err) if err.kind() == ErrorKind::NotFound => Err(PathDoesNotExist),
+            Err(err) if err.kind() == ErrorKind::PermissionDenied => Err(NoPermission),
             Err(err) => {
                 eprintln!("{}", err);
                 Err(InvalidPath)
             }
+        }
+    }
+
+    // Query the canonical path once; if it vanished after canonicalizing, report that instead of
+    // mistaking it for a non-directory
+    fn from_canonical(name: String, path: PathBuf) -> Result<MediaLocationInfo, MediaPathError> {
+        match path.metadata() {
+            Ok(metadata) if metadata.is_dir() => {
+                let name = if name.trim().is_empty() {
+                    Self::name_from_path(&path)
+                } else {
+                    name
+                };
+                let capacity = Capacity::query(&path);
+                let read_only = path_is_read_only(&path);
+                let file_id = FileId::of(&path, &metadata);
+                Ok(MediaLocationInfo {
+                    name,
+                    path,
+                    dropdown_opened: false,
+                    capacity,
+                    offline: false,
+                    read_only,
+                    color: None,
+                    color_picker_open: false,
+                    archived: false,
+                    edit: None,
+                    file_id,
+                })
+            }
+            Ok(_) => Err(NotADirectory),
+            Err(err) if err.kind() == ErrorKind::NotFound => Err(PathDoesNotExist),
+            Err(_err) => Err(NoPermission),
+        }
+    }
+
+    // Used when no name was given: the directory name, or the whole path for `/`
+    fn name_from_path(path: &Path) -> String {
+        match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => path.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Runs `new` on a blocking thread so a slow or stalled mount can't freeze the UI.
+    pub async fn new_async(
+        name: String,
+        location: String,
+    ) -> Result<MediaLocationInfo, MediaPathError> {
+        async_std::task::spawn_blocking(move || MediaLocationInfo::new(name, location)).await
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn capacity(&self) -> Option<Capacity> {
+        self.capacity
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn color(&self) -> Option<[f32; 3]> {
+        self.color
+    }
+
+    pub fn is_color_picker_open(&self) -> bool {
+        self.color_picker_open
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    pub fn is_dropdown_opened(&self) -> bool {
+        self.dropdown_opened
+    }
+
+    pub fn edit(&self) -> Option<&LocationEdit> {
+        self.edit.as_ref()
+    }
+
+    fn refresh_status(&mut self) {
+        self.set_status(LocationStatus::query(&self.path));
+    }
+
+    fn set_status(&mut self, status: LocationStatus) {
+        self.offline = status.offline;
+        self.capacity = status.capacity;
+        self.read_only = status.read_only;
+        self.file_id = status.file_id;
+    }
+
+    /// Whether both point at the same directory. Filesystem identity catches two spellings of one
+    /// folder on a case-insensitive volume (e.g. a FAT or exFAT card), without treating distinct
+    /// folders on a case-sensitive one as the same.
+    pub fn is_same_location(&self, other: &MediaLocationInfo) -> bool {
+        self.path == other.path
+            || matches!((self.file_id, other.file_id), (Some(a), Some(b)) if a == b)
+    }
+}
+
+/// A directory's identity on its filesystem: device and inode on Unix, volume serial number and
+/// file index on Windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileId {
+    volume: u64,
+    index: u64,
+}
+
+impl FileId {
+    #[cfg(unix)]
+    fn of(_path: &Path, metadata: &std::fs::Metadata) -> Option<FileId> {
+        use std::os::unix::fs::MetadataExt;
+
+        Some(FileId {
+            volume: metadata.dev(),
+            index: metadata.ino(),
+        })
+    }
+
+    #[cfg(windows)]
+    fn of(path: &Path, _metadata: &std::fs::Metadata) -> Option<FileId> {
+        use std::os::windows::fs::OpenOptionsExt;
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::Storage::FileSystem::{
+            GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
         };
+
+        // Directories can only be opened with backup semantics; no access is needed to query them
+        let file = std::fs::OpenOptions::new()
+            .access_mode(0)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)
+            .ok()?;
+
+        // SAFETY: `info` is plain data that the call fills in
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        // SAFETY: `file` stays open for the duration of the call
+        if unsafe { GetFileInformationByHandle(file.as_raw_handle() as isize, &mut info) } == 0 {
+            return None;
+        }
+
+        Some(FileId {
+            volume: u64::from(info.dwVolumeSerialNumber),
+            index: (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow),
+        })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn of(_path: &Path, _metadata: &std::fs::Metadata) -> Option<FileId> {
+        None
+    }
+}
+
+/// Whether a location's path is reachable, and what it's like if so. Every check touches the
+/// filesystem, so the app queries it with `query_async` or `query_all` rather than on the UI thread.
+#[derive(Debug, Clone, Copy)]
+pub struct LocationStatus {
+    offline: bool,
+    capacity: Option<Capacity>,
+    read_only: bool,
+    file_id: Option<FileId>,
+}
+
+impl LocationStatus {
+    pub fn query(path: &Path) -> LocationStatus {
+        let metadata = path.metadata().ok();
+        let offline = metadata.is_none();
+
+        LocationStatus {
+            offline,
+            file_id: metadata.and_then(|metadata| FileId::of(path, &metadata)),
+            capacity: if offline { None } else { Capacity::query(path) },
+            read_only: !offline && path_is_read_only(path),
+        }
+    }
+
+    /// Runs `query` on a blocking thread so a stalled mount can't freeze the UI.
+    pub async fn query_async(path: PathBuf) -> LocationStatus {
+        async_std::task::spawn_blocking(move || LocationStatus::query(&path)).await
+    }
+
+    /// Like `query_async`, for several paths on one thread.
+    pub async fn query_all(paths: Vec<PathBuf>) -> Vec<(PathBuf, LocationStatus)> {
+        async_std::task::spawn_blocking(move || {
+            paths
+                .into_iter()
+                .map(|path| {
+                    let status = LocationStatus::query(&path);
+                    (path, status)
+                })
+                .collect()
+        })
+        .await
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
     }
+}
+
+/// How a path is stored on disk. Most paths are plain strings; ones that aren't valid Unicode
+/// (common on camera cards formatted elsewhere) keep their raw platform encoding instead.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SerializedPath {
+    Utf8(String),
+    UnixBytes { unix_bytes: Vec<u8> },
+    WindowsWide { windows_wide: Vec<u16> },
+}
+
+fn serialize_path_buf<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    let serialized = match path.to_str() {
+        Some(path) => SerializedPath::Utf8(path.to_owned()),
+        #[cfg(unix)]
+        None => {
+            use std::os::unix::ffi::OsStrExt;
+            SerializedPath::UnixBytes {
+                unix_bytes: path.as_os_str().as_bytes().to_vec(),
+            }
+        }
+        #[cfg(windows)]
+        None => {
+            use std::os::windows::ffi::OsStrExt;
+            SerializedPath::WindowsWide {
+                windows_wide: path.as_os_str().encode_wide().collect(),
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        None => SerializedPath::Utf8(path.to_string_lossy().into_owned()),
+    };
+
+    serialized.serialize(serializer)
+}
+
+fn deserialize_path_buf<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    let path = match SerializedPath::deserialize(deserializer)? {
+        SerializedPath::Utf8(path) => PathBuf::from(path),
+        #[cfg(unix)]
+        SerializedPath::UnixBytes { unix_bytes } => {
+            use std::os::unix::ffi::OsStringExt;
+            PathBuf::from(std::ffi::OsString::from_vec(unix_bytes))
+        }
+        #[cfg(windows)]
+        SerializedPath::WindowsWide { windows_wide } => {
+            use std::os::windows::ffi::OsStringExt;
+            PathBuf::from(std::ffi::OsString::from_wide(&windows_wide))
+        }
+        // Saved on another platform. The lossy path won't exist here, so the location is marked
+        // offline rather than failing the whole file.
+        #[cfg(not(unix))]
+        SerializedPath::UnixBytes { unix_bytes } => {
+            PathBuf::from(String::from_utf8_lossy(&unix_bytes).into_owned())
+        }
+        #[cfg(not(windows))]
+        SerializedPath::WindowsWide { windows_wide } => {
+            PathBuf::from(String::from_utf16_lossy(&windows_wide))
+        }
+    };
 
-    fn view_header(&self) -> Element<MediaPathMessage> {
-        container(
-            row![
-                column![
-                    text(self.name.to_string()).size(25),
-                    text(self.path.to_str().unwrap_or("Error")).size(15),
-                ]
-                .spacing(5)
-                .width(Fill),
-                row![
-                    button("Edit"),
-                    button("Remove").on_press(MediaPathMessage::Remove)
-                ]
-                .align_items(Alignment::Center)
-                .spacing(4)
-            ]
-            .padding(4)
-            .align_items(Alignment::Center),
-        )
-        .into()
-    }
-
-    fn view_media(&self) -> Element<MediaPathMessage> {
-        self.view_as_accordion(
-            text(self.name.to_string()).size(25).width(Fill).into(),
-            column![text("Option1"), text("Option2")].into(),
-        )
-    }
-
-    fn view_as_accordion<'a>(
-        &self,
-        header: Element<'a, MediaPathMessage>,
-        body: Element<'a, MediaPathMessage>,
-    ) -> Element<'a, MediaPathMessage> {
-        let header = row![
-            header,
-            button("Toggle").on_press(MediaPathMessage::ToggleAccordion)
-        ]
-        .align_items(Alignment::Center);
-        let wrapper = if self.dropdown_opened {
-            container(column![header, body].spacing(4))
+    Ok(path)
+}
+
+/// Expands a leading `~` or `~user` and resolves relative paths against the home directory,
+/// since the working directory of a GUI app isn't meaningful to the user.
+pub fn expand_location(location: &str) -> PathBuf {
+    let home = directories_next::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+
+    if let Some(rest) = location.strip_prefix('~') {
+        let (user, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        let user_home = if user.is_empty() {
+            home
         } else {
-            container(header)
+            home_of_user(user)
         };
 
-        wrapper
-            .padding(4)
-            .width(Fill)
-            .style(|theme: &Theme| {
-                let palette = theme.extended_palette();
+        return match user_home {
+            Some(user_home) => user_home.join(rest),
+            None => PathBuf::from(location),
+        };
+    }
+
+    let path = PathBuf::from(location);
+    match home {
+        Some(home) if path.is_relative() => home.join(path),
+        _ => path,
+    }
+}
+
+/// Whether the current user can't write inside `path`. `access` also reports read-only mounts,
+/// which the permission bits alone don't show.
+#[cfg(unix)]
+fn path_is_read_only(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
 
-                container::Appearance::default().with_background(palette.background.weak.color)
-                //TODO: Implement a stylesheet to round the corner of the container
-            })
-            .into()
+    match CString::new(path.as_os_str().as_bytes()) {
+        // SAFETY: `path` is a valid NUL-terminated string that outlives the call
+        Ok(path) => unsafe { libc::access(path.as_ptr(), libc::W_OK) != 0 },
+        Err(_) => false,
     }
 }
 
+#[cfg(not(unix))]
+fn path_is_read_only(path: &Path) -> bool {
+    path.metadata()
+        .map(|metadata| metadata.permissions().readonly())
+        .unwrap_or(false)
+}
+
+/// Looks `user` up in the user database, which also covers NSS sources like LDAP that
+/// `/etc/passwd` alone misses.
+#[cfg(unix)]
+fn home_of_user(user: &str) -> Option<PathBuf> {
+    use std::ffi::{CStr, CString, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    let user = CString::new(user).ok()?;
+    let mut buffer = vec![0 as libc::c_char; 1024];
+
+    loop {
+        // SAFETY: `passwd` is plain data that `getpwnam_r` fills in
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: every pointer is valid for the call, and `buffer.len()` is its real size
+        let err = unsafe {
+            libc::getpwnam_r(
+                user.as_ptr(),
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+
+        // The entry's strings didn't fit; retry with more room
+        if err == libc::ERANGE && buffer.len() < 1 << 20 {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if err != 0 || result.is_null() || passwd.pw_dir.is_null() {
+            return None;
+        }
+
+        // SAFETY: `pw_dir` points at a NUL-terminated string inside `buffer`
+        let home = unsafe { CStr::from_ptr(passwd.pw_dir) };
+        return Some(PathBuf::from(OsStr::from_bytes(home.to_bytes())));
+    }
+}
+
+#[cfg(not(unix))]
+fn home_of_user(_user: &str) -> Option<PathBuf> {
+    None
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MediaPathList {
     list: Vec<MediaLocationInfo>,
@@ -125,37 +471,14 @@ impl MediaPathList {
         self.list.push(path)
     }
 
-    pub fn view_headers(&self) -> Element<Message> {
-        return if self.list.is_empty().not() {
-            container(
-                Column::with_children(self.list.iter().enumerate().map(|(i, path)| {
-                    path.view_header()
-                        .map(move |message| Message::MediaPathMessage(i, message))
-                }))
-                .spacing(10),
-            )
-            .style(|theme: &Theme| {
-                let palette = theme.extended_palette();
-
-                container::Appearance::default().with_border(palette.background.strong.color, 1)
-            })
-            .into()
-        } else {
-            container(column!(text("No paths...").size(25)).height(200))
+    /// Adds a location unless its directory is already in the list.
+    pub fn try_push(&mut self, location_info: MediaLocationInfo) -> Result<(), MediaPathError> {
+        if self.contains(&location_info) {
+            return Err(Duplicate);
         }
-        .padding(20)
-        .into();
-    }
 
-    pub fn view_media(&self) -> Element<Message> {
-        scrollable(
-            Column::with_children(self.list.iter().enumerate().map(|(i, path)| {
-                path.view_media()
-                    .map(move |message| Message::MediaPathMessage(i, message))
-            }))
-            .spacing(10),
-        )
-        .into()
+        self.list.push(location_info);
+        Ok(())
     }
 
     pub fn remove(&mut self, index: usize) {
@@ -166,6 +489,228 @@ impl MediaPathList {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&MediaLocationInfo> {
+        self.list.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MediaLocationInfo> {
+        self.list.iter()
+    }
+
+    /// Appends the locations from `other` whose directories aren't already in the list, returning how
+    /// many were added.
+    pub fn merge(&mut self, other: MediaPathList) -> usize {
+        let before = self.list.len();
+
+        for location_info in other.list {
+            if !self.contains(&location_info) {
+                self.list.push(location_info);
+            }
+        }
+
+        self.list.len() - before
+    }
+
+    /// The index of the location pointing at the same directory, see `is_same_location`.
+    pub fn find(&self, location_info: &MediaLocationInfo) -> Option<usize> {
+        self.list
+            .iter()
+            .position(|existing| existing.is_same_location(location_info))
+    }
+
+    pub fn contains(&self, location_info: &MediaLocationInfo) -> bool {
+        self.find(location_info).is_some()
+    }
+
+    pub fn contains_path(&self, path: &Path) -> bool {
+        self.list
+            .iter()
+            .any(|location_info| location_info.path == path)
+    }
+
+    pub fn refresh_status(&mut self) {
+        for location_info in self.list.iter_mut() {
+            location_info.refresh_status();
+        }
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.list
+            .iter()
+            .map(|location_info| location_info.path.clone())
+            .collect()
+    }
+
+    /// Applies a status from `LocationStatus::query_all`. It's matched by path, since locations
+    /// may have been moved or removed while it was queried.
+    pub fn set_status(&mut self, path: &Path, status: LocationStatus) {
+        for location_info in self.list.iter_mut() {
+            if location_info.path == path {
+                location_info.set_status(status);
+            }
+        }
+    }
+
+    pub fn move_up(&mut self, index: usize) {
+        if index > 0 && index < self.list.len() {
+            self.list.swap(index - 1, index);
+        } else {
+            eprintln!("Tried to move MediaPath up out of bounds");
+        }
+    }
+
+    pub fn move_down(&mut self, index: usize) {
+        if index + 1 < self.list.len() {
+            self.list.swap(index, index + 1);
+        } else {
+            eprintln!("Tried to move MediaPath down out of bounds");
+        }
+    }
+
+    pub fn rename(&mut self, index: usize, name: String) -> Result<(), MediaPathError> {
+        if name.trim().is_empty() {
+            return Err(EmptyName);
+        }
+
+        self.list.get_mut(index).expect("Invalid Index!").name = name;
+        Ok(())
+    }
+
+    pub fn begin_edit(&mut self, index: usize) {
+        let location_info = self.list.get_mut(index).expect("Invalid Index!");
+        location_info.edit = Some(LocationEdit {
+            name: location_info.name.clone(),
+            path: location_info.path.to_string_lossy().into_owned(),
+            validating: false,
+        });
+    }
+
+    pub fn set_edit_name(&mut self, index: usize, name: String) {
+        if let Some(edit) = &mut self.list.get_mut(index).expect("Invalid Index!").edit {
+            edit.name = name;
+        }
+    }
+
+    pub fn set_edit_path(&mut self, index: usize, path: String) {
+        if let Some(edit) = &mut self.list.get_mut(index).expect("Invalid Index!").edit {
+            edit.path = path;
+        }
+    }
+
+    pub fn cancel_edit(&mut self, index: usize) {
+        self.list.get_mut(index).expect("Invalid Index!").edit = None;
+    }
+
+    /// Applies the pending edit if it only renames the location. When the path changed too, the
+    /// edit is returned so the caller can validate it (e.g. with `MediaLocationInfo::new_async`)
+    /// and finish with `finish_edit`. On error the location stays in edit mode. Does nothing while
+    /// a previous commit is still being validated.
+    pub fn commit_edit(&mut self, index: usize) -> Result<Option<LocationEdit>, MediaPathError> {
+        let location_info = self.list.get_mut(index).expect("Invalid Index!");
+        let Some(edit) = &mut location_info.edit else {
+            return Ok(None);
+        };
+        if edit.validating {
+            return Ok(None);
+        }
+        let edit = edit.clone();
+
+        if edit.name.trim().is_empty() {
+            return Err(EmptyName);
+        }
+
+        // Compare the text form `begin_edit` filled in, so an untouched non-UTF-8 path still counts
+        // as unchanged
+        if edit.path != location_info.path.to_string_lossy() {
+            if let Some(edit) = &mut location_info.edit {
+                edit.validating = true;
+            }
+            return Ok(Some(edit));
+        }
+
+        self.rename(index, edit.name)?;
+        self.cancel_edit(index);
+        Ok(None)
+    }
+
+    /// Finishes a `commit_edit` whose path was validated. The location is found by the path it had
+    /// when the edit was committed, since it may have moved in the meantime. Returns whether it
+    /// was updated; a location that was removed or left edit mode since is ignored. On error it
+    /// stays in edit mode.
+    pub fn finish_edit(
+        &mut self,
+        original: &Path,
+        result: Result<MediaLocationInfo, MediaPathError>,
+    ) -> Result<bool, MediaPathError> {
+        let Some(index) = self.list.iter().position(|location_info| {
+            location_info.path == original
+                && location_info
+                    .edit
+                    .as_ref()
+                    .is_some_and(|edit| edit.validating)
+        }) else {
+            return Ok(false);
+        };
+
+        let result = result.and_then(|validated| self.replace_location(index, validated));
+        if let Err(err) = result {
+            if let Some(edit) = &mut self.list[index].edit {
+                edit.validating = false;
+            }
+            return Err(err);
+        }
+
+        Ok(true)
+    }
+
+    /// Points a location at a newly validated path, keeping its label color and other settings.
+    /// Fails if another location already uses that path.
+    pub fn replace_location(
+        &mut self,
+        index: usize,
+        validated: MediaLocationInfo,
+    ) -> Result<(), MediaPathError> {
+        if self.find(&validated).is_some_and(|other| other != index) {
+            return Err(Duplicate);
+        }
+
+        let location_info = self.list.get_mut(index).expect("Invalid Index!");
+        location_info.name = validated.name;
+        location_info.path = validated.path;
+        location_info.capacity = validated.capacity;
+        location_info.offline = validated.offline;
+        location_info.read_only = validated.read_only;
+        location_info.file_id = validated.file_id;
+        location_info.edit = None;
+        Ok(())
+    }
+
+    pub fn set_color_picker_open(&mut self, index: usize, open: bool) {
+        self.list
+            .get_mut(index)
+            .expect("Invalid Index!")
+            .color_picker_open = open;
+    }
+
+    pub fn set_color(&mut self, index: usize, color: Option<[f32; 3]>) {
+        let location_info = self.list.get_mut(index).expect("Invalid Index!");
+        location_info.color = color;
+        location_info.color_picker_open = false;
+    }
+
+    pub fn toggle_archived(&mut self, index: usize) {
+        let location_info = self.list.get_mut(index).expect("Invalid Index!");
+        location_info.archived = !location_info.archived;
+    }
+
     pub fn toggle_accordion(&mut self, index: usize) {
         let location_info = self.list.get_mut(index).expect("Invalid Index!");
         location_info.dropdown_opened = !location_info.dropdown_opened;
@@ -194,4 +739,45 @@ pub enum MediaPathError {
     PathDoesNotExist,
     NoPermission,
     NotADirectory,
+    EmptyName,
+    Duplicate,
+}
+
+impl fmt::Display for MediaPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            NoError => "",
+            InvalidPath => "Invalid path",
+            PathDoesNotExist => "Path does not exist",
+            NoPermission => "No permission",
+            NotADirectory => "Not a directory",
+            EmptyName => "Name can't be empty",
+            Duplicate => "Location already added",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for MediaPathError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_removed_after_canonicalize_does_not_exist() {
+        let media = tempfile::tempdir().unwrap();
+        let card = media.path().join("card");
+        std::fs::create_dir(&card).unwrap();
+        let canonical = card.canonicalize().unwrap();
+
+        // The card is pulled between `canonicalize` and the metadata query in `new`
+        std::fs::remove_dir(&card).unwrap();
+
+        assert!(matches!(
+            MediaLocationInfo::from_canonical(String::new(), canonical),
+            Err(PathDoesNotExist)
+        ));
+    }
 }
@@ -1,10 +1,24 @@
-use crate::State;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use turbosql::serde_json;
+use turbosql::serde_json::Value;
+
+use crate::media_location::MediaPathList;
+
+/// Version written to `state.json`. Bump it and add a step to `migrate` whenever a change to
+/// the saved state can't be handled by `#[serde(default)]` alone.
+pub const STATE_VERSION: u32 = 1;
 
 #[derive(Debug, Clone)]
 pub enum LoadError {
     File,
-    Format,
+    // Carries what serde couldn't make sense of
+    Format(String),
+    // Written by a newer release that this one doesn't know how to read
+    Version(u32),
 }
 
 #[derive(Debug, Clone)]
@@ -12,64 +26,322 @@ pub enum SaveError {
     File,
     Write,
     Format,
+    Rename,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::File => f.write_str("state file couldn't be opened"),
+            LoadError::Format(detail) => write!(f, "state file couldn't be parsed: {}", detail),
+            LoadError::Version(version) => write!(
+                f,
+                "state file is version {}, newer than the supported version {}",
+                version, STATE_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::File => f.write_str("state file couldn't be created"),
+            SaveError::Write => f.write_str("state file couldn't be written"),
+            SaveError::Format => f.write_str("state couldn't be serialized"),
+            SaveError::Rename => f.write_str("state file couldn't be replaced"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// Environment variable that overrides the data directory, e.g. for a portable install.
+pub const DATA_DIR_ENV: &str = "MEDIA_MANAGER_DATA_DIR";
+
+/// Picks the directory holding `state.json`: an explicit `--data-dir` wins, then
+/// `MEDIA_MANAGER_DATA_DIR`, then the platform data directory. Empty values are ignored, since
+/// they'd put `state.json` in the working directory.
+pub fn resolve_data_dir(flag: Option<PathBuf>) -> PathBuf {
+    let non_empty = |path: &PathBuf| !path.as_os_str().is_empty();
+
+    flag.filter(non_empty)
+        .or_else(|| {
+            std::env::var_os(DATA_DIR_ENV)
+                .map(PathBuf::from)
+                .filter(non_empty)
+        })
+        .unwrap_or_else(default_data_dir)
+}
+
+fn default_data_dir() -> PathBuf {
+    if let Some(project_dirs) =
+        directories_next::ProjectDirs::from("me", "zoarial", "media_manager")
+    {
+        project_dirs.data_dir().into()
+    } else {
+        std::env::current_dir().unwrap_or_default()
+    }
+}
+
+/// Where `load` and `save` keep the state file inside `data_dir`.
+pub fn state_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("state.json")
 }
+
+/// How many rotating backups `save` keeps unless told otherwise.
+pub const DEFAULT_BACKUPS: usize = 3;
+/// Upper bound on rotating backups; `load` looks for at most this many.
+pub const MAX_BACKUPS: usize = 10;
+
+/// A state read by `load`. If `state.json` was unusable, it also has the backup the state came
+/// from and why `state.json` couldn't be used.
+#[derive(Debug, Clone)]
+pub struct Loaded<T> {
+    pub state: T,
+    pub recovered_from: Option<PathBuf>,
+    pub recovered_because: Option<LoadError>,
+}
+
+/// Reads `state.json` from `data_dir`, upgrading older versions before deserializing it as `T`.
+/// If it's missing or unreadable, the newest backup that parses is used instead. A file from a
+/// newer release is reported as `LoadError::Version` without trying the backups.
 #[cfg(not(target_arch = "wasm32"))]
-impl State {
-    fn path() -> std::path::PathBuf {
-        let mut path = if let Some(project_dirs) =
-            directories_next::ProjectDirs::from("me", "zoarial", "media_manager")
-        {
-            project_dirs.data_dir().into()
-        } else {
-            std::env::current_dir().unwrap_or_default()
+pub async fn load<T: DeserializeOwned>(data_dir: PathBuf) -> Result<Loaded<T>, LoadError> {
+    let path = state_path(&data_dir);
+
+    let error = match read_contents(&path).await {
+        Ok(contents) => match parse(&contents) {
+            Ok(state) => {
+                return Ok(Loaded {
+                    state,
+                    recovered_from: None,
+                    recovered_because: None,
+                })
+            }
+            Err(err) => {
+                back_up_unreadable(&path, &contents).await;
+                // A newer release's file isn't damaged; recovering an older backup in its place
+                // would only downgrade it on the next save
+                if let LoadError::Version(_) = err {
+                    return Err(err);
+                }
+                err
+            }
+        },
+        Err(err) => err,
+    };
+
+    for n in 1..=MAX_BACKUPS {
+        let backup = backup_path(&path, n);
+        let Ok(contents) = read_contents(&backup).await else {
+            continue;
         };
 
-        path.push("state.json");
+        if let Ok(state) = parse(&contents) {
+            return Ok(Loaded {
+                state,
+                recovered_from: Some(backup),
+                recovered_because: Some(error),
+            });
+        }
+    }
+
+    Err(error)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_contents(path: &Path) -> Result<String, LoadError> {
+    async_std::fs::read_to_string(path)
+        .await
+        .map_err(|_| LoadError::File)
+}
+
+fn parse<T: DeserializeOwned>(contents: &str) -> Result<T, LoadError> {
+    let value: Value = serde_json::from_str(contents)
+        .map_err(|err| LoadError::Format(format!("invalid JSON: {}", err)))?;
 
-        path
+    let version = version_of(&value);
+    if version > STATE_VERSION {
+        return Err(LoadError::Version(version));
     }
 
-    pub(crate) async fn load() -> Result<State, LoadError> {
-        use async_std::prelude::*;
+    serde_json::from_value(migrate(value))
+        .map_err(|err| LoadError::Format(format!("unexpected state shape: {}", err)))
+}
 
-        let mut contents = String::new();
+// Files from before the version field are version 0
+fn version_of(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| u32::try_from(version).unwrap_or(u32::MAX))
+}
 
-        let mut file = async_std::fs::File::open(Self::path())
+// `state.json.bak.1` is the newest backup
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    path.with_file_name(format!("state.json.bak.{}", n))
+}
+
+// Where the current state.json is copied before it's replaced
+fn staged_backup_path(path: &Path) -> PathBuf {
+    path.with_file_name("state.json.bak.new")
+}
+
+// Copies the current state.json aside before it's replaced. It only becomes `.bak.1` in
+// `rotate_backups`, once the replacement succeeded.
+#[cfg(not(target_arch = "wasm32"))]
+async fn stage_backup(path: &Path, backups: usize) -> Option<PathBuf> {
+    if backups == 0 || !path.exists() {
+        return None;
+    }
+
+    let staged = staged_backup_path(path);
+    match async_std::fs::copy(path, &staged).await {
+        Ok(_) => Some(staged),
+        Err(err) => {
+            eprintln!("Failed to back up state before saving: {}", err);
+            None
+        }
+    }
+}
+
+// Shifts each backup up by one and moves the staged copy to `.bak.1`. Backups beyond `backups`
+// are left over from a higher setting and removed, so `load` can't recover stale state from them.
+// Failures are only logged, since the save itself already succeeded.
+#[cfg(not(target_arch = "wasm32"))]
+async fn rotate_backups(path: &Path, staged: Option<PathBuf>, backups: usize) {
+    for n in backups + 1..=MAX_BACKUPS {
+        let _ = async_std::fs::remove_file(backup_path(path, n)).await;
+    }
+
+    let Some(staged) = staged else {
+        return;
+    };
+
+    let _ = async_std::fs::remove_file(backup_path(path, backups)).await;
+    for n in (1..backups).rev() {
+        let _ = async_std::fs::rename(backup_path(path, n), backup_path(path, n + 1)).await;
+    }
+
+    if let Err(err) = async_std::fs::rename(&staged, backup_path(path, 1)).await {
+        eprintln!("Failed to keep a backup of the previous state: {}", err);
+    }
+}
+
+// Keeps a copy of a state file we can't read, since the caller falls back to a fresh state
+// that will overwrite it on the next save
+#[cfg(not(target_arch = "wasm32"))]
+async fn back_up_unreadable(path: &Path, contents: &str) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let backup = path.with_file_name(format!("state.json.invalid-{}", timestamp));
+
+    if let Err(err) = async_std::fs::write(&backup, contents).await {
+        eprintln!("Failed to back up unreadable state: {}", err);
+    }
+}
+
+/// Writes `state` to `state.json` in `data_dir`, stamped with the current `STATE_VERSION`. The
+/// previous file is kept as the newest of `backups` rotating backups (at most `MAX_BACKUPS`).
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn save<T: Serialize>(
+    data_dir: PathBuf,
+    state: T,
+    backups: usize,
+) -> Result<(), SaveError> {
+    use async_std::prelude::*;
+
+    let mut value = serde_json::to_value(&state).map_err(|_| SaveError::Format)?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert(String::from("version"), Value::from(STATE_VERSION));
+    }
+
+    let json = serde_json::to_string_pretty(&value).map_err(|_| SaveError::Format)?;
+
+    let path = state_path(&data_dir);
+
+    if let Some(dir) = path.parent() {
+        async_std::fs::create_dir_all(dir)
             .await
-            .map_err(|_| LoadError::File)?;
+            .map_err(|_| SaveError::File)?;
+    }
+
+    // Write a sibling file and rename it over the real one, so an interrupted save leaves the
+    // previous state.json intact
+    let temp_path = path.with_extension("json.tmp");
 
-        file.read_to_string(&mut contents)
+    {
+        let mut file = async_std::fs::File::create(&temp_path)
             .await
-            .map_err(|_| LoadError::File)?;
+            .map_err(|_| SaveError::File)?;
+
+        file.write_all(json.as_bytes())
+            .await
+            .map_err(|_| SaveError::Write)?;
+
+        file.sync_all().await.map_err(|_| SaveError::Write)?;
+    }
+
+    // The backups are only shifted once state.json was replaced, so failed saves (and their
+    // retries) can't push the history out
+    let backups = backups.min(MAX_BACKUPS);
+    let staged = stage_backup(&path, backups).await;
 
-        serde_json::from_str(&contents).map_err(|_| LoadError::Format)
+    if async_std::fs::rename(&temp_path, &path).await.is_err() {
+        if let Some(staged) = staged {
+            let _ = async_std::fs::remove_file(staged).await;
+        }
+        return Err(SaveError::Rename);
     }
 
-    pub(crate) async fn save(self) -> Result<(), SaveError> {
-        use async_std::prelude::*;
+    rotate_backups(&path, staged, backups).await;
 
-        println!("Saving...");
+    Ok(())
+}
 
-        let json = serde_json::to_string_pretty(&self).map_err(|_| SaveError::Format)?;
+/// Writes just the location list to `file`, so it can be imported on another machine.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn export_locations(file: PathBuf, list: MediaPathList) -> Result<(), SaveError> {
+    let json = serde_json::to_string_pretty(&list).map_err(|_| SaveError::Format)?;
 
-        let path = Self::path();
+    async_std::fs::write(file, json)
+        .await
+        .map_err(|_| SaveError::Write)
+}
 
-        if let Some(dir) = path.parent() {
-            async_std::fs::create_dir_all(dir)
-                .await
-                .map_err(|_| SaveError::File)?;
-        }
+/// Reads a list written by `export_locations`. Paths that don't exist here are kept and marked
+/// offline rather than failing the whole import.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn import_locations(file: PathBuf) -> Result<MediaPathList, LoadError> {
+    let contents = async_std::fs::read_to_string(file)
+        .await
+        .map_err(|_| LoadError::File)?;
 
-        {
-            let mut file = async_std::fs::File::create(path)
-                .await
-                .map_err(|_| SaveError::File)?;
+    let mut list: MediaPathList =
+        serde_json::from_str(&contents).map_err(|err| LoadError::Format(err.to_string()))?;
 
-            file.write_all(json.as_bytes())
-                .await
-                .map_err(|_| SaveError::Write)?;
-        }
+    async_std::task::spawn_blocking(move || {
+        list.refresh_status();
+        Ok(list)
+    })
+    .await
+}
+
+/// Upgrades older `state.json` shapes to the current `STATE_VERSION`.
+fn migrate(mut value: Value) -> Value {
+    let version = version_of(&value);
 
-        Ok(())
+    if version < 1 {
+        // v0 files predate the version field; every field added since has a serde default
+        if let Some(object) = value.as_object_mut() {
+            object.insert(String::from("version"), Value::from(1));
+        }
     }
+
+    value
 }
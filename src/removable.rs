@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use iced::Subscription;
+
+use crate::Message;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Polls the system's mount points and emits `Message::RemovableDetected` for each new one.
+///
+/// Mounts present when the subscription starts are treated as already known, so only media
+/// inserted while the app is running triggers a prompt.
+pub fn watch() -> Subscription<Message> {
+    struct Watch;
+
+    iced::subscription::unfold(
+        std::any::TypeId::of::<Watch>(),
+        None,
+        |known: Option<HashSet<PathBuf>>| async move {
+            let mut known = match known {
+                Some(known) => known,
+                None => mount_points().await.into_iter().collect(),
+            };
+
+            loop {
+                async_std::task::sleep(POLL_INTERVAL).await;
+
+                let current: HashSet<PathBuf> = mount_points().await.into_iter().collect();
+                let added = current.difference(&known).next().cloned();
+
+                // Forget unmounted paths so re-inserting the same card is noticed again
+                known.retain(|path| current.contains(path));
+
+                if let Some(path) = added {
+                    known.insert(path.clone());
+                    return (Message::RemovableDetected(path), Some(known));
+                }
+            }
+        },
+    )
+}
+
+#[cfg(target_os = "linux")]
+async fn mount_points() -> Vec<PathBuf> {
+    const REMOVABLE_PREFIXES: [&str; 3] = ["/media/", "/run/media/", "/mnt/"];
+
+    let Ok(mounts) = async_std::fs::read_to_string("/proc/mounts").await else {
+        return Vec::new();
+    };
+
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(unescape_mount_point)
+        .filter(|mount_point| {
+            REMOVABLE_PREFIXES
+                .iter()
+                .any(|prefix| mount_point.starts_with(prefix))
+        })
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+async fn mount_points() -> Vec<PathBuf> {
+    use async_std::prelude::*;
+
+    let Ok(mut entries) = async_std::fs::read_dir("/Volumes").await else {
+        return Vec::new();
+    };
+
+    let mut mount_points = Vec::new();
+    while let Some(Ok(entry)) = entries.next().await {
+        mount_points.push(entry.path().into());
+    }
+    mount_points
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn mount_points() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+// /proc/mounts escapes spaces, tabs, newlines and backslashes as octal (e.g. `\040`)
+#[cfg(target_os = "linux")]
+fn unescape_mount_point(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+            match u8::from_str_radix(&code, 8) {
+                Ok(byte) => unescaped.push(byte as char),
+                Err(_) => {
+                    unescaped.push(c);
+                    unescaped.push_str(&code);
+                }
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+
+    unescaped
+}
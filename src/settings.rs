@@ -0,0 +1,198 @@
+use std::fmt;
+
+use iced::Theme;
+use media_manager::persistence;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// Detected once; asking the desktop environment on every frame is too slow
+static SYSTEM_THEME: Lazy<Theme> = Lazy::new(|| match dark_light::detect() {
+    dark_light::Mode::Dark => Theme::Dark,
+    dark_light::Mode::Light | dark_light::Mode::Default => Theme::Light,
+});
+
+/// Tokens that can appear in an organize pattern, e.g. `{year}/{month}/{day}`.
+pub const PATTERN_TOKENS: [&str; 5] = ["year", "month", "day", "camera", "location"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSettings {
+    pub destination: String,
+    pub pattern: String,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        ImportSettings {
+            destination: String::new(),
+            pattern: String::from("{year}/{month}/{day}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    UnknownToken(String),
+    UnclosedBrace,
+    UnopenedBrace,
+    NestedBrace,
+}
+
+pub fn validate_pattern(pattern: &str) -> Result<(), PatternError> {
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(PatternError::UnopenedBrace);
+        }
+
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            return Err(PatternError::UnclosedBrace);
+        };
+
+        let token = &after[..end];
+        if token.contains('{') {
+            return Err(PatternError::NestedBrace);
+        }
+        if !PATTERN_TOKENS.contains(&token) {
+            return Err(PatternError::UnknownToken(token.to_string()));
+        }
+
+        rest = &after[end + 1..];
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemePreference {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    pub const ALL: [ThemePreference; 3] = [
+        ThemePreference::System,
+        ThemePreference::Light,
+        ThemePreference::Dark,
+    ];
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemePreference::System => SYSTEM_THEME.clone(),
+            ThemePreference::Light => Theme::Light,
+            ThemePreference::Dark => Theme::Dark,
+        }
+    }
+
+    /// The explicit opposite of whatever is showing now, for the quick toggle.
+    pub fn toggled(self) -> ThemePreference {
+        if self.theme() == Theme::Dark {
+            ThemePreference::Light
+        } else {
+            ThemePreference::Dark
+        }
+    }
+}
+
+impl fmt::Display for ThemePreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ThemePreference::System => "System",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+        })
+    }
+}
+
+/// How many rotating backups of state.json are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BackupCount(pub usize);
+
+impl BackupCount {
+    pub const CHOICES: [BackupCount; 5] = [
+        BackupCount(0),
+        BackupCount(1),
+        BackupCount(persistence::DEFAULT_BACKUPS),
+        BackupCount(5),
+        BackupCount(persistence::MAX_BACKUPS),
+    ];
+}
+
+impl Default for BackupCount {
+    fn default() -> Self {
+        BackupCount(persistence::DEFAULT_BACKUPS)
+    }
+}
+
+impl fmt::Display for BackupCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("None"),
+            1 => f.write_str("1 backup"),
+            n => write!(f, "{} backups", n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_patterns() {
+        for pattern in [
+            "",
+            "photos",
+            "{year}/{month}/{day}",
+            "{camera}/{year}",
+            "a {location} b",
+        ] {
+            assert_eq!(validate_pattern(pattern), Ok(()), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn unknown_token() {
+        assert_eq!(
+            validate_pattern("{year}/{lens}"),
+            Err(PatternError::UnknownToken(String::from("lens")))
+        );
+    }
+
+    #[test]
+    fn empty_token() {
+        assert_eq!(
+            validate_pattern("{}"),
+            Err(PatternError::UnknownToken(String::new()))
+        );
+    }
+
+    #[test]
+    fn unclosed_brace() {
+        assert_eq!(
+            validate_pattern("{year}/{month"),
+            Err(PatternError::UnclosedBrace)
+        );
+    }
+
+    #[test]
+    fn stray_closing_brace() {
+        assert_eq!(
+            validate_pattern("year}/{month}"),
+            Err(PatternError::UnopenedBrace)
+        );
+    }
+
+    #[test]
+    fn nested_braces() {
+        assert_eq!(validate_pattern("{{year}}"), Err(PatternError::NestedBrace));
+        assert_eq!(
+            validate_pattern("{day{year}}"),
+            Err(PatternError::NestedBrace)
+        );
+    }
+}
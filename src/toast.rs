@@ -0,0 +1,75 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{button, column, container, row, text};
+use iced::{Alignment, Element, Length, Theme};
+
+use crate::Message;
+
+/// How long a toast stays up unless it's closed first.
+const TOAST_LIFETIME: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+struct Toast {
+    id: u64,
+    text: String,
+    created: Instant,
+}
+
+/// Errors shown to the user in the corner of the window, oldest first.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Toasts {
+    queue: Vec<Toast>,
+    next_id: u64,
+}
+
+impl Toasts {
+    pub(crate) fn push(&mut self, text: impl Into<String>) {
+        self.queue.push(Toast {
+            id: self.next_id,
+            text: text.into(),
+            created: Instant::now(),
+        });
+        self.next_id += 1;
+    }
+
+    pub(crate) fn dismiss(&mut self, id: u64) {
+        self.queue.retain(|toast| toast.id != id);
+    }
+
+    pub(crate) fn expire(&mut self, now: Instant) {
+        self.queue
+            .retain(|toast| now.duration_since(toast.created) < TOAST_LIFETIME);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub(crate) fn view(&self) -> Element<'_, Message> {
+        column(self.queue.iter().map(|toast| {
+            container(
+                row![
+                    text(&toast.text).width(Length::Fill),
+                    button("x").on_press(Message::DismissToast(toast.id)),
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center),
+            )
+            .width(300)
+            .padding(10)
+            .style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+
+                container::Appearance {
+                    text_color: Some(palette.danger.weak.text),
+                    ..container::Appearance::default()
+                }
+                .with_background(palette.danger.weak.color)
+                .with_border(palette.danger.strong.color, 1)
+            })
+            .into()
+        }))
+        .spacing(10)
+        .into()
+    }
+}
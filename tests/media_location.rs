@@ -0,0 +1,402 @@
+use std::fs;
+
+use async_std::task::block_on;
+use media_manager::media_location::{
+    expand_location, LocationStatus, MediaLocationInfo, MediaPathError, MediaPathList,
+};
+
+fn list_with_location(root: &std::path::Path) -> MediaPathList {
+    let path = root.join("card");
+    fs::create_dir_all(&path).unwrap();
+
+    let mut list = MediaPathList::default();
+    list.push(
+        MediaLocationInfo::new(String::from("Card"), path.to_string_lossy().into_owned()).unwrap(),
+    );
+    list
+}
+
+#[test]
+fn edit_with_same_path_renames() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+
+    list.begin_edit(0);
+    list.set_edit_name(0, String::from("Renamed"));
+
+    assert_eq!(list.commit_edit(0).unwrap(), None);
+    assert_eq!(list.get(0).unwrap().name(), "Renamed");
+    assert!(list.get(0).unwrap().edit().is_none());
+}
+
+#[test]
+fn edit_with_empty_name_stays_in_edit_mode() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+
+    list.begin_edit(0);
+    list.set_edit_name(0, String::from("  "));
+
+    assert!(matches!(
+        list.commit_edit(0),
+        Err(MediaPathError::EmptyName)
+    ));
+    assert_eq!(list.get(0).unwrap().name(), "Card");
+    assert!(list.get(0).unwrap().edit().is_some());
+}
+
+#[test]
+fn edit_with_new_path_is_validated_then_replaced() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    list.set_color(0, Some([1.0, 0.0, 0.0]));
+    let moved = media.path().join("moved");
+    fs::create_dir(&moved).unwrap();
+
+    list.begin_edit(0);
+    list.set_edit_path(0, moved.to_string_lossy().into_owned());
+    let edit = list
+        .commit_edit(0)
+        .unwrap()
+        .expect("path change needs validation");
+    let validated = block_on(MediaLocationInfo::new_async(edit.name, edit.path)).unwrap();
+    list.replace_location(0, validated).unwrap();
+
+    let location_info = list.get(0).unwrap();
+    assert_eq!(location_info.path(), moved.canonicalize().unwrap());
+    assert_eq!(location_info.name(), "Card");
+    assert_eq!(location_info.color(), Some([1.0, 0.0, 0.0]));
+    assert!(location_info.edit().is_none());
+}
+
+#[test]
+fn validated_edit_follows_the_location_when_it_moves() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    let other = media.path().join("other");
+    let moved = media.path().join("moved");
+    fs::create_dir(&other).unwrap();
+    fs::create_dir(&moved).unwrap();
+    list.push(
+        MediaLocationInfo::new(String::from("Other"), other.to_string_lossy().into_owned())
+            .unwrap(),
+    );
+    let original = list.get(0).unwrap().path().to_path_buf();
+
+    list.begin_edit(0);
+    list.set_edit_path(0, moved.to_string_lossy().into_owned());
+    let edit = list.commit_edit(0).unwrap().unwrap();
+    // A second Save while the first is validated does nothing
+    assert_eq!(list.commit_edit(0).unwrap(), None);
+    // The card moves down and the location now at its old index starts an edit too
+    list.move_down(0);
+    list.begin_edit(0);
+    let validated = MediaLocationInfo::new(edit.name, edit.path);
+
+    assert!(list.finish_edit(&original, validated).unwrap());
+    assert_eq!(list.get(1).unwrap().path(), moved.canonicalize().unwrap());
+    assert_eq!(list.get(0).unwrap().path(), other.canonicalize().unwrap());
+    assert!(list.get(0).unwrap().edit().is_some());
+}
+
+#[test]
+fn validated_edit_of_a_removed_location_is_ignored() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    let moved = media.path().join("moved");
+    fs::create_dir(&moved).unwrap();
+    let original = list.get(0).unwrap().path().to_path_buf();
+
+    list.begin_edit(0);
+    list.set_edit_path(0, moved.to_string_lossy().into_owned());
+    let edit = list.commit_edit(0).unwrap().unwrap();
+    list.remove(0);
+
+    let validated = MediaLocationInfo::new(edit.name, edit.path);
+    assert!(!list.finish_edit(&original, validated).unwrap());
+    assert!(list.is_empty());
+}
+
+#[test]
+fn edit_with_bad_path_reports_the_same_error_as_adding() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    let file = media.path().join("photo.jpg");
+    fs::write(&file, b"").unwrap();
+
+    list.begin_edit(0);
+    list.set_edit_path(0, file.to_string_lossy().into_owned());
+    let edit = list.commit_edit(0).unwrap().unwrap();
+
+    assert!(matches!(
+        MediaLocationInfo::new(edit.name, edit.path),
+        Err(MediaPathError::NotADirectory)
+    ));
+}
+
+#[test]
+fn new_accepts_a_directory() {
+    let media = tempfile::tempdir().unwrap();
+
+    let location_info =
+        MediaLocationInfo::new(String::new(), media.path().to_string_lossy().into_owned()).unwrap();
+
+    assert_eq!(location_info.path(), media.path().canonicalize().unwrap());
+}
+
+#[test]
+fn new_derives_a_blank_name_from_the_directory() {
+    let media = tempfile::tempdir().unwrap();
+    let card = media.path().join("EOS_DIGITAL");
+    fs::create_dir(&card).unwrap();
+
+    let location_info =
+        MediaLocationInfo::new(String::new(), card.to_string_lossy().into_owned()).unwrap();
+
+    assert_eq!(location_info.name(), "EOS_DIGITAL");
+}
+
+#[test]
+fn new_derives_a_whitespace_name_from_the_directory() {
+    let media = tempfile::tempdir().unwrap();
+    let card = media.path().join("EOS_DIGITAL");
+    fs::create_dir(&card).unwrap();
+
+    let location_info =
+        MediaLocationInfo::new(String::from(" \t "), card.to_string_lossy().into_owned()).unwrap();
+
+    assert_eq!(location_info.name(), "EOS_DIGITAL");
+}
+
+#[test]
+fn new_keeps_a_given_name() {
+    let media = tempfile::tempdir().unwrap();
+
+    let location_info = MediaLocationInfo::new(
+        String::from("SD Card"),
+        media.path().to_string_lossy().into_owned(),
+    )
+    .unwrap();
+
+    assert_eq!(location_info.name(), "SD Card");
+}
+
+#[test]
+fn new_rejects_a_blank_path() {
+    for location in ["", "   "] {
+        assert!(matches!(
+            MediaLocationInfo::new(String::new(), String::from(location)),
+            Err(MediaPathError::InvalidPath)
+        ));
+    }
+}
+
+fn home() -> std::path::PathBuf {
+    directories_next::BaseDirs::new()
+        .unwrap()
+        .home_dir()
+        .to_path_buf()
+}
+
+#[test]
+fn expand_location_resolves_tilde_to_home() {
+    assert_eq!(expand_location("~"), home());
+    assert_eq!(expand_location("~/Pictures"), home().join("Pictures"));
+}
+
+#[test]
+fn expand_location_resolves_relative_paths_against_home() {
+    assert_eq!(expand_location("./sub"), home().join("sub"));
+    assert_eq!(expand_location("sub/dir"), home().join("sub/dir"));
+}
+
+#[cfg(unix)]
+#[test]
+fn expand_location_keeps_absolute_paths() {
+    assert_eq!(
+        expand_location("/media/card"),
+        std::path::Path::new("/media/card")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn expand_location_resolves_tilde_user_to_their_home() {
+    use std::ffi::{CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    // The current user's entry, so the test doesn't depend on what other accounts exist
+    let (user, user_home) = unsafe {
+        let passwd = libc::getpwuid(libc::geteuid());
+        assert!(!passwd.is_null());
+        (
+            CStr::from_ptr((*passwd).pw_name)
+                .to_str()
+                .unwrap()
+                .to_owned(),
+            std::path::PathBuf::from(OsStr::from_bytes(
+                CStr::from_ptr((*passwd).pw_dir).to_bytes(),
+            )),
+        )
+    };
+
+    assert_eq!(expand_location(&format!("~{}", user)), user_home);
+    assert_eq!(
+        expand_location(&format!("~{}/Pictures", user)),
+        user_home.join("Pictures")
+    );
+}
+
+#[test]
+fn expand_location_keeps_an_unknown_user_literal() {
+    assert_eq!(
+        expand_location("~no-such-user-here/x"),
+        std::path::Path::new("~no-such-user-here/x")
+    );
+}
+
+#[test]
+fn new_reports_a_missing_path() {
+    let media = tempfile::tempdir().unwrap();
+    let missing = media.path().join("not here");
+
+    assert!(matches!(
+        MediaLocationInfo::new(String::new(), missing.to_string_lossy().into_owned()),
+        Err(MediaPathError::PathDoesNotExist)
+    ));
+}
+
+#[test]
+fn new_reports_a_file_as_not_a_directory() {
+    let media = tempfile::tempdir().unwrap();
+    let file = media.path().join("photo.jpg");
+    fs::write(&file, b"").unwrap();
+
+    assert!(matches!(
+        MediaLocationInfo::new(String::new(), file.to_string_lossy().into_owned()),
+        Err(MediaPathError::NotADirectory)
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn new_reports_an_unreadable_parent_as_no_permission() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Root ignores permission bits, so there's nothing to test
+    if unsafe { libc::geteuid() } == 0 {
+        return;
+    }
+
+    let media = tempfile::tempdir().unwrap();
+    let locked = media.path().join("locked");
+    fs::create_dir_all(locked.join("inner")).unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+    let result = MediaLocationInfo::new(
+        String::new(),
+        locked.join("inner").to_string_lossy().into_owned(),
+    );
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+    assert!(matches!(result, Err(MediaPathError::NoPermission)));
+}
+
+#[test]
+fn adding_the_same_directory_twice_is_a_duplicate() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    // A different spelling of the same directory canonicalizes to the same path
+    let again = media.path().join("card/../card");
+
+    let result = list.try_push(
+        MediaLocationInfo::new(String::from("Again"), again.to_string_lossy().into_owned())
+            .unwrap(),
+    );
+
+    assert!(matches!(result, Err(MediaPathError::Duplicate)));
+    assert_eq!(list.len(), 1);
+}
+
+#[test]
+fn the_same_directory_under_another_name_is_a_duplicate() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    // Stands in for a case-insensitive card, where `card` and `CARD` are one directory: after the
+    // rename the stored path differs, but the directory itself is the same
+    let renamed = media.path().join("CARD");
+    fs::rename(media.path().join("card"), &renamed).unwrap();
+
+    let result = list.try_push(
+        MediaLocationInfo::new(String::new(), renamed.to_string_lossy().into_owned()).unwrap(),
+    );
+
+    assert!(matches!(result, Err(MediaPathError::Duplicate)));
+}
+
+#[test]
+fn directories_differing_only_in_case_are_distinct() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    let upper = media.path().join("CARD");
+    // Only meaningful on a case-sensitive filesystem
+    if fs::create_dir(&upper).is_err() {
+        return;
+    }
+
+    let result = list.try_push(
+        MediaLocationInfo::new(String::new(), upper.to_string_lossy().into_owned()).unwrap(),
+    );
+
+    assert!(result.is_ok());
+    assert_eq!(list.len(), 2);
+}
+
+#[test]
+fn editing_onto_another_locations_path_is_a_duplicate() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    let other = media.path().join("other");
+    fs::create_dir(&other).unwrap();
+    list.try_push(
+        MediaLocationInfo::new(String::from("Other"), other.to_string_lossy().into_owned())
+            .unwrap(),
+    )
+    .unwrap();
+
+    let card = MediaLocationInfo::new(
+        String::from("Other"),
+        media.path().join("card").to_string_lossy().into_owned(),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        list.replace_location(1, card),
+        Err(MediaPathError::Duplicate)
+    ));
+    assert_eq!(list.get(1).unwrap().path(), other.canonicalize().unwrap());
+}
+
+#[test]
+fn status_is_applied_by_path_after_the_list_changes() {
+    let media = tempfile::tempdir().unwrap();
+    let mut list = list_with_location(media.path());
+    let other = media.path().join("other");
+    fs::create_dir(&other).unwrap();
+    list.try_push(
+        MediaLocationInfo::new(String::new(), other.to_string_lossy().into_owned()).unwrap(),
+    )
+    .unwrap();
+
+    let statuses = block_on(LocationStatus::query_all(list.paths()));
+    fs::remove_dir(media.path().join("card")).unwrap();
+    let statuses_after_removal = block_on(LocationStatus::query_all(list.paths()));
+    // The card location moves while its status is being checked
+    list.move_down(0);
+    for (path, status) in statuses.into_iter().chain(statuses_after_removal) {
+        list.set_status(&path, status);
+    }
+
+    assert!(!list.get(0).unwrap().is_offline());
+    assert!(list.get(1).unwrap().is_offline());
+}
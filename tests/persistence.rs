@@ -0,0 +1,412 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use async_std::task::block_on;
+use media_manager::media_location::{MediaLocationInfo, MediaPathList};
+use media_manager::persistence::{self, LoadError, Loaded, SaveError, STATE_VERSION};
+use serde::{Deserialize, Serialize};
+use turbosql::serde_json::{self, Value};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TestState {
+    media_path_list: MediaPathList,
+    media_location: String,
+}
+
+fn location(root: &Path, dir: &str, name: &str) -> MediaLocationInfo {
+    let path = root.join(dir);
+    fs::create_dir_all(&path).unwrap();
+    MediaLocationInfo::new(name.to_string(), path.to_string_lossy().into_owned()).unwrap()
+}
+
+#[test]
+fn round_trip_keeps_persisted_fields() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let media = tempfile::tempdir().unwrap();
+
+    let mut state = TestState {
+        media_location: String::from("~/Pictures"),
+        ..TestState::default()
+    };
+    state
+        .media_path_list
+        .push(location(media.path(), "sd card", "SD Card"));
+    state
+        .media_path_list
+        .push(location(media.path(), "Fotos \"Ürlaub\" #1", "Ürlaub 📷"));
+    state
+        .media_path_list
+        .push(location(media.path(), "a/b/c", "Nested"));
+    state.media_path_list.set_color(1, Some([0.25, 0.5, 1.0]));
+    state.media_path_list.toggle_archived(2);
+    state.media_path_list.expand_accordion(0);
+
+    block_on(persistence::save(data_dir.path().to_path_buf(), &state, 3)).unwrap();
+    let loaded: TestState = block_on(persistence::load(data_dir.path().to_path_buf()))
+        .unwrap()
+        .state;
+
+    assert_eq!(loaded.media_location, state.media_location);
+    assert_eq!(loaded.media_path_list.len(), state.media_path_list.len());
+    for (loaded, saved) in loaded
+        .media_path_list
+        .iter()
+        .zip(state.media_path_list.iter())
+    {
+        assert_eq!(loaded.name(), saved.name());
+        assert_eq!(loaded.path(), saved.path());
+        assert_eq!(loaded.color(), saved.color());
+        assert_eq!(loaded.is_archived(), saved.is_archived());
+        assert_eq!(loaded.is_dropdown_opened(), saved.is_dropdown_opened());
+    }
+}
+
+#[test]
+fn save_stamps_version_and_leaves_no_temp_file() {
+    let data_dir = tempfile::tempdir().unwrap();
+
+    block_on(persistence::save(
+        data_dir.path().to_path_buf(),
+        TestState::default(),
+        3,
+    ))
+    .unwrap();
+
+    let contents = fs::read_to_string(persistence::state_path(data_dir.path())).unwrap();
+    let value: Value = serde_json::from_str(&contents).unwrap();
+    assert_eq!(value["version"], Value::from(STATE_VERSION));
+    assert!(!data_dir.path().join("state.json.tmp").exists());
+}
+
+// One test, since the environment is shared by every test in this binary
+#[test]
+fn data_dir_prefers_flag_then_env_and_ignores_empty_values() {
+    let flag = PathBuf::from("/flag/dir");
+    let env = PathBuf::from("/env/dir");
+
+    std::env::set_var(persistence::DATA_DIR_ENV, &env);
+    assert_eq!(persistence::resolve_data_dir(Some(flag.clone())), flag);
+    assert_eq!(persistence::resolve_data_dir(None), env);
+    assert_eq!(persistence::resolve_data_dir(Some(PathBuf::new())), env);
+
+    std::env::set_var(persistence::DATA_DIR_ENV, "");
+    let default = persistence::resolve_data_dir(None);
+    assert_ne!(default, PathBuf::new());
+    assert_eq!(persistence::resolve_data_dir(Some(PathBuf::new())), default);
+
+    std::env::remove_var(persistence::DATA_DIR_ENV);
+    assert_eq!(persistence::resolve_data_dir(None), default);
+}
+
+#[test]
+fn missing_file_is_a_file_error() {
+    let data_dir = tempfile::tempdir().unwrap();
+
+    let result: Result<Loaded<TestState>, _> =
+        block_on(persistence::load(data_dir.path().to_path_buf()));
+
+    assert!(matches!(result, Err(LoadError::File)));
+}
+
+#[test]
+fn unreadable_file_is_backed_up() {
+    let data_dir = tempfile::tempdir().unwrap();
+    fs::write(persistence::state_path(data_dir.path()), "{ not json").unwrap();
+
+    let result: Result<Loaded<TestState>, _> =
+        block_on(persistence::load(data_dir.path().to_path_buf()));
+
+    assert!(matches!(result, Err(LoadError::Format(_))));
+    let backups = fs::read_dir(data_dir.path())
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("state.json.invalid-")
+        })
+        .count();
+    assert_eq!(backups, 1);
+}
+
+#[test]
+fn import_merges_and_keeps_missing_paths_offline() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let media = tempfile::tempdir().unwrap();
+    let file = data_dir.path().join("locations.json");
+
+    let mut exported = MediaPathList::default();
+    exported.push(location(media.path(), "kept", "Kept"));
+    exported.push(location(media.path(), "gone", "Gone"));
+    block_on(persistence::export_locations(
+        file.clone(),
+        exported.clone(),
+    ))
+    .unwrap();
+    fs::remove_dir(media.path().join("gone")).unwrap();
+
+    let mut existing = MediaPathList::default();
+    existing.push(location(media.path(), "kept", "Already here"));
+    let imported = block_on(persistence::import_locations(file)).unwrap();
+    let added = existing.merge(imported);
+
+    assert_eq!(added, 1);
+    assert_eq!(existing.get(0).unwrap().name(), "Already here");
+    assert_eq!(existing.get(1).unwrap().name(), "Gone");
+    assert!(existing.get(1).unwrap().is_offline());
+}
+
+#[cfg(unix)]
+#[test]
+fn import_keeps_paths_from_another_platform_offline() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let media = tempfile::tempdir().unwrap();
+    let file = data_dir.path().join("locations.json");
+    let kept = location(media.path(), "kept", "Kept");
+    // `D:\DCIM\` followed by an unpaired surrogate, as saved on Windows
+    let windows_wide: Vec<u16> = "D:\\DCIM\\".encode_utf16().chain([0xd800]).collect();
+    fs::write(
+        &file,
+        serde_json::json!({
+            "list": [
+                { "name": "Kept", "path": kept.path() },
+                { "name": "Windows", "path": { "windows_wide": windows_wide } },
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let imported = block_on(persistence::import_locations(file)).unwrap();
+
+    assert_eq!(imported.len(), 2);
+    assert!(!imported.get(0).unwrap().is_offline());
+    assert_eq!(imported.get(1).unwrap().name(), "Windows");
+    assert!(imported.get(1).unwrap().is_offline());
+}
+
+#[test]
+fn saves_rotate_up_to_the_backup_count() {
+    let data_dir = tempfile::tempdir().unwrap();
+
+    for n in 0..4 {
+        let state = TestState {
+            media_location: n.to_string(),
+            ..TestState::default()
+        };
+        block_on(persistence::save(data_dir.path().to_path_buf(), state, 2)).unwrap();
+    }
+
+    let saved_location = |file: &str| {
+        let contents = fs::read_to_string(data_dir.path().join(file)).unwrap();
+        serde_json::from_str::<TestState>(&contents)
+            .unwrap()
+            .media_location
+    };
+    assert_eq!(saved_location("state.json"), "3");
+    assert_eq!(saved_location("state.json.bak.1"), "2");
+    assert_eq!(saved_location("state.json.bak.2"), "1");
+    assert!(!data_dir.path().join("state.json.bak.3").exists());
+}
+
+#[test]
+fn failed_saves_keep_the_backups() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let save = |n: usize| {
+        let state = TestState {
+            media_location: n.to_string(),
+            ..TestState::default()
+        };
+        block_on(persistence::save(data_dir.path().to_path_buf(), state, 2))
+    };
+    save(0).unwrap();
+    save(1).unwrap();
+    save(2).unwrap();
+
+    // A directory in the way of the temp file makes every save fail before it's written
+    fs::create_dir(data_dir.path().join("state.json.tmp")).unwrap();
+    for n in 3..7 {
+        assert!(save(n).is_err());
+    }
+
+    let saved_location = |file: &str| {
+        let contents = fs::read_to_string(data_dir.path().join(file)).unwrap();
+        serde_json::from_str::<TestState>(&contents)
+            .unwrap()
+            .media_location
+    };
+    assert_eq!(saved_location("state.json"), "2");
+    assert_eq!(saved_location("state.json.bak.1"), "1");
+    assert_eq!(saved_location("state.json.bak.2"), "0");
+}
+
+#[test]
+fn failed_rename_keeps_the_backups() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let path = persistence::state_path(data_dir.path());
+    let save = |n: usize| {
+        let state = TestState {
+            media_location: n.to_string(),
+            ..TestState::default()
+        };
+        block_on(persistence::save(data_dir.path().to_path_buf(), state, 2))
+    };
+    save(0).unwrap();
+    save(1).unwrap();
+    save(2).unwrap();
+
+    // A non-empty directory can't be replaced by the new file
+    fs::remove_file(&path).unwrap();
+    fs::create_dir_all(path.join("in the way")).unwrap();
+    for n in 3..7 {
+        assert!(matches!(save(n), Err(SaveError::Rename)));
+    }
+
+    let saved_location = |file: &str| {
+        let contents = fs::read_to_string(data_dir.path().join(file)).unwrap();
+        serde_json::from_str::<TestState>(&contents)
+            .unwrap()
+            .media_location
+    };
+    assert_eq!(saved_location("state.json.bak.1"), "1");
+    assert_eq!(saved_location("state.json.bak.2"), "0");
+    assert!(!data_dir.path().join("state.json.bak.new").exists());
+}
+
+#[test]
+fn lowering_the_backup_count_removes_extra_backups() {
+    let data_dir = tempfile::tempdir().unwrap();
+
+    for backups in [3, 3, 3, 3, 1] {
+        block_on(persistence::save(
+            data_dir.path().to_path_buf(),
+            TestState::default(),
+            backups,
+        ))
+        .unwrap();
+    }
+
+    assert!(data_dir.path().join("state.json.bak.1").exists());
+    assert!(!data_dir.path().join("state.json.bak.2").exists());
+    assert!(!data_dir.path().join("state.json.bak.3").exists());
+}
+
+#[test]
+fn unreadable_file_falls_back_to_newest_valid_backup() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let path = persistence::state_path(data_dir.path());
+    fs::write(data_dir.path().join("state.json.bak.1"), "{ not json").unwrap();
+    fs::write(
+        data_dir.path().join("state.json.bak.2"),
+        r#"{ "version": 1, "media_path_list": { "list": [] }, "media_location": "backup" }"#,
+    )
+    .unwrap();
+    fs::write(&path, "{ not json").unwrap();
+
+    let loaded: Loaded<TestState> =
+        block_on(persistence::load(data_dir.path().to_path_buf())).unwrap();
+
+    assert_eq!(loaded.state.media_location, "backup");
+    assert_eq!(
+        loaded.recovered_from,
+        Some(data_dir.path().join("state.json.bak.2"))
+    );
+    assert!(matches!(
+        loaded.recovered_because,
+        Some(LoadError::Format(_))
+    ));
+}
+
+#[test]
+fn v0_fixture_loads_and_is_upgraded_on_save() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let path = persistence::state_path(data_dir.path());
+    fs::copy("tests/fixtures/state_v0.json", &path).unwrap();
+
+    let loaded: Loaded<TestState> =
+        block_on(persistence::load(data_dir.path().to_path_buf())).unwrap();
+    let state = loaded.state;
+
+    assert!(loaded.recovered_from.is_none());
+    assert_eq!(state.media_location, "/home/user/Pictures");
+    let names: Vec<_> = state.media_path_list.iter().map(|l| l.name()).collect();
+    assert_eq!(names, ["SD Card", "Phone backup"]);
+    assert_eq!(
+        state.media_path_list.get(0).unwrap().path(),
+        Path::new("/media/user/EOS_DIGITAL")
+    );
+    assert!(!state.media_path_list.get(0).unwrap().is_archived());
+
+    block_on(persistence::save(data_dir.path().to_path_buf(), &state, 0)).unwrap();
+    let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(value["version"], Value::from(STATE_VERSION));
+}
+
+#[test]
+fn newer_version_is_a_version_error() {
+    let data_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        persistence::state_path(data_dir.path()),
+        r#"{ "version": 99, "media_path_list": { "list": [] }, "media_location": "" }"#,
+    )
+    .unwrap();
+
+    let result: Result<Loaded<TestState>, _> =
+        block_on(persistence::load(data_dir.path().to_path_buf()));
+
+    assert!(matches!(result, Err(LoadError::Version(99))));
+}
+
+#[test]
+fn newer_version_is_not_replaced_by_a_backup() {
+    let data_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        persistence::state_path(data_dir.path()),
+        r#"{ "version": 99, "media_path_list": { "list": [] }, "media_location": "newer" }"#,
+    )
+    .unwrap();
+    fs::write(
+        data_dir.path().join("state.json.bak.1"),
+        r#"{ "version": 1, "media_path_list": { "list": [] }, "media_location": "backup" }"#,
+    )
+    .unwrap();
+
+    let result: Result<Loaded<TestState>, _> =
+        block_on(persistence::load(data_dir.path().to_path_buf()));
+
+    assert!(matches!(result, Err(LoadError::Version(99))));
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths_round_trip() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let data_dir = tempfile::tempdir().unwrap();
+    let media = tempfile::tempdir().unwrap();
+    let raw = media.path().join(OsStr::from_bytes(b"DCIM \xff\xfe card"));
+
+    let mut state = TestState::default();
+    state
+        .media_path_list
+        .push(location(media.path(), "plain", "Plain"));
+    state.media_path_list.push(
+        serde_json::from_value(serde_json::json!({
+            "name": "Raw",
+            "path": { "unix_bytes": raw.as_os_str().as_bytes() },
+        }))
+        .unwrap(),
+    );
+
+    block_on(persistence::save(data_dir.path().to_path_buf(), &state, 0)).unwrap();
+    let contents = fs::read_to_string(persistence::state_path(data_dir.path())).unwrap();
+    let value: Value = serde_json::from_str(&contents).unwrap();
+    let loaded: Loaded<TestState> =
+        block_on(persistence::load(data_dir.path().to_path_buf())).unwrap();
+
+    // Valid paths stay plain strings so older files and hand edits keep working
+    assert!(value["media_path_list"]["list"][0]["path"].is_string());
+    assert_eq!(loaded.state.media_path_list.get(1).unwrap().path(), raw);
+}